@@ -0,0 +1,363 @@
+use std::os::unix::io::RawFd;
+
+use super::Input;
+use crate::{
+    errors::{Error, Result},
+    key::{self, Key},
+    keyboard::{self, KeyInput},
+    layout::Layout,
+    mouse::{MouseButton, MouseInput, PressType},
+};
+
+pub const EV_SYN: u16 = 0x00;
+pub const EV_KEY: u16 = 0x01;
+pub const EV_REL: u16 = 0x02;
+pub const EV_ABS: u16 = 0x03;
+
+pub const SYN_REPORT: u16 = 0x00;
+
+pub const REL_X: u16 = 0x00;
+pub const REL_Y: u16 = 0x01;
+pub const REL_HWHEEL: u16 = 0x06;
+pub const REL_WHEEL: u16 = 0x08;
+pub const REL_WHEEL_HI_RES: u16 = 0x0B;
+pub const REL_HWHEEL_HI_RES: u16 = 0x0C;
+
+pub const ABS_X: u16 = 0x00;
+pub const ABS_Y: u16 = 0x01;
+
+pub const BTN_LEFT: u16 = 0x110;
+pub const BTN_RIGHT: u16 = 0x111;
+pub const BTN_MIDDLE: u16 = 0x112;
+pub const BTN_SIDE: u16 = 0x113;
+pub const BTN_EXTRA: u16 = 0x114;
+
+// one wheel notch, same as windows WHEEL_DELTA and evdev hi-res wheel units
+const WHEEL_DELTA: i32 = 120;
+
+/// One evdev event, the payload of a `struct input_event`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Event {
+    pub type_: u16,
+    pub code: u16,
+    pub value: i32,
+}
+
+impl Event {
+    pub fn new(type_: u16, code: u16, value: i32) -> Event {
+        Event { type_, code, value }
+    }
+
+    fn syn() -> Event {
+        Event::new(EV_SYN, SYN_REPORT, 0)
+    }
+}
+
+pub fn button_code(button: &MouseButton) -> u16 {
+    match button {
+        MouseButton::ButtonLeft => BTN_LEFT,
+        MouseButton::ButtonRight => BTN_RIGHT,
+        MouseButton::ButtonMiddle => BTN_MIDDLE,
+        MouseButton::ButtonX1 => BTN_SIDE,
+        MouseButton::ButtonX2 => BTN_EXTRA,
+    }
+}
+
+fn push_wheel(amount: i32, code: u16, hi_res_code: u16, events: &mut Vec<Event>) {
+    if amount == 0 {
+        return;
+    }
+    let mut notches = (amount.abs() + WHEEL_DELTA / 2) / WHEEL_DELTA;
+    notches = notches.max(1) * amount.signum();
+    events.push(Event::new(EV_REL, hi_res_code, amount));
+    events.push(Event::new(EV_REL, code, notches));
+    events.push(Event::syn());
+}
+
+fn push_key(key: Key, value: i32, events: &mut Vec<Event>) -> Result<()> {
+    let code = match key.to_evdev() {
+        Some(code) => code,
+        None => bail!("no evdev key code for key {:?}", key),
+    };
+    events.push(Event::new(EV_KEY, code, value));
+    events.push(Event::syn());
+    Ok(())
+}
+
+fn push_scancode(scancode: u16, value: i32, events: &mut Vec<Event>) -> Result<()> {
+    let code = match key::scancode_to_evdev(scancode) {
+        Some(code) => code,
+        None => bail!("no evdev key code for scan code {:#x}", scancode),
+    };
+    events.push(Event::new(EV_KEY, code, value));
+    events.push(Event::syn());
+    Ok(())
+}
+
+fn push_stroke(key: Key, events: &mut Vec<Event>) -> Result<()> {
+    push_key(key, 1, events)?;
+    push_key(key, 0, events)
+}
+
+// type `c` with the modifiers of `layout`, None for the system layout
+fn push_char(c: char, layout: Option<&Layout>, events: &mut Vec<Event>) -> Result<()> {
+    let (key, modifiers) = match keyboard::parse_char(c, layout) {
+        Ok(key_mods) => key_mods,
+        Err(e) => bail!("cannot type {:?} for unicode input: {}", c, e),
+    };
+    let modifier_keys = keyboard::char_modifier_keys(modifiers, &[]);
+    for modifier in &modifier_keys {
+        push_key(*modifier, 1, events)?;
+    }
+    push_stroke(key, events)?;
+    for modifier in modifier_keys.iter().rev() {
+        push_key(*modifier, 0, events)?;
+    }
+    Ok(())
+}
+
+// the kernel has no unicode input, type ctrl+shift+u, the hex code point and
+// space, which the IBus and GTK input methods turn into the char. u and the
+// hex digits are typed where `layout` has them, like shift+1 on AZERTY.
+fn push_unicode(c: char, layout: Option<&Layout>, events: &mut Vec<Event>) -> Result<()> {
+    let (u, _) = match keyboard::parse_char('u', layout) {
+        Ok(key_mods) => key_mods,
+        Err(e) => bail!("cannot type 'u' for unicode input: {}", e),
+    };
+    push_key(Key::LCtrl, 1, events)?;
+    push_key(Key::LShift, 1, events)?;
+    push_stroke(u, events)?;
+    push_key(Key::LShift, 0, events)?;
+    push_key(Key::LCtrl, 0, events)?;
+    for digit in format!("{:x}", c as u32).chars() {
+        push_char(digit, layout, events)?;
+    }
+    push_stroke(Key::Space, events)
+}
+
+/// Append the evdev events of one input, each report ends with `SYN_REPORT`.
+/// Unicode chars are typed with the keys of `layout`, None for the system one.
+pub fn encode(input: &Input, layout: Option<&Layout>, events: &mut Vec<Event>) -> Result<()> {
+    match input {
+        Input::Key(KeyInput::KeyDown(key)) => push_key(*key, 1, events)?,
+        Input::Key(KeyInput::KeyUp(key)) => push_key(*key, 0, events)?,
+        Input::Key(KeyInput::Unicode(c)) => push_unicode(*c, layout, events)?,
+        Input::Key(KeyInput::ScanCodeDown(code)) => push_scancode(*code, 1, events)?,
+        Input::Key(KeyInput::ScanCodeUp(code)) => push_scancode(*code, 0, events)?,
+        // waited by `backend::input`
+        Input::Key(KeyInput::Delay(_)) => {}
+        Input::Mouse(mouse_input) => match mouse_input {
+            MouseInput::MoveTo(x, y) => {
+                events.push(Event::new(EV_ABS, ABS_X, *x));
+                events.push(Event::new(EV_ABS, ABS_Y, *y));
+                events.push(Event::syn());
+            }
+            MouseInput::MoveDelta(x, y) => {
+                events.push(Event::new(EV_REL, REL_X, *x));
+                events.push(Event::new(EV_REL, REL_Y, *y));
+                events.push(Event::syn());
+            }
+            MouseInput::Press(button, press) => {
+                let code = button_code(button);
+                if let PressType::PressDown | PressType::Click = press {
+                    events.push(Event::new(EV_KEY, code, 1));
+                    events.push(Event::syn());
+                }
+                if let PressType::PressUp | PressType::Click = press {
+                    events.push(Event::new(EV_KEY, code, 0));
+                    events.push(Event::syn());
+                }
+            }
+            MouseInput::Wheel(amount) => push_wheel(*amount, REL_WHEEL, REL_WHEEL_HI_RES, events),
+            MouseInput::HWheel(amount) => {
+                push_wheel(*amount, REL_HWHEEL, REL_HWHEEL_HI_RES, events)
+            }
+        },
+    }
+    Ok(())
+}
+
+/// Write events as `struct input_event` records to `fd`
+pub fn write_events(fd: RawFd, events: &[Event]) -> Result<()> {
+    let records: Vec<libc::input_event> = events
+        .iter()
+        .map(|e| libc::input_event {
+            time: libc::timeval {
+                tv_sec: 0,
+                tv_usec: 0,
+            },
+            type_: e.type_,
+            code: e.code,
+            value: e.value,
+        })
+        .collect();
+    let size = std::mem::size_of_val(records.as_slice());
+    let written = unsafe { libc::write(fd, records.as_ptr() as *const libc::c_void, size) };
+    if written != size as isize {
+        bail!(Error::with_chain(
+            std::io::Error::last_os_error(),
+            "write() input events failed"
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode_all(inputs: &[Input]) -> Result<Vec<Event>> {
+        encode_on(inputs, None)
+    }
+
+    fn encode_on(inputs: &[Input], layout: Option<&Layout>) -> Result<Vec<Event>> {
+        let mut events = Vec::new();
+        for input in inputs {
+            encode(input, layout, &mut events)?;
+        }
+        Ok(events)
+    }
+
+    fn key_events(events: &[Event]) -> Vec<(u16, i32)> {
+        events
+            .iter()
+            .filter(|e| e.type_ == EV_KEY)
+            .map(|e| (e.code, e.value))
+            .collect()
+    }
+
+    #[test]
+    fn encode_keys() -> Result<()> {
+        let events = encode_all(&[
+            Input::Key(KeyInput::KeyDown(Key::Ctrl)),
+            Input::Key(KeyInput::KeyUp(Key::C)),
+        ])?;
+        assert_eq!(
+            events,
+            vec![
+                Event::new(EV_KEY, 29, 1),
+                Event::syn(),
+                Event::new(EV_KEY, 46, 0),
+                Event::syn(),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn encode_unicode() -> Result<()> {
+        let keys = key_events(&encode_all(&[Input::Key(KeyInput::Unicode('中'))])?);
+        // ctrl+shift+u 4 e 2 d space
+        assert_eq!(
+            keys,
+            vec![
+                (29, 1),
+                (42, 1),
+                (22, 1),
+                (22, 0),
+                (42, 0),
+                (29, 0),
+                (5, 1),
+                (5, 0),
+                (18, 1),
+                (18, 0),
+                (3, 1),
+                (3, 0),
+                (32, 1),
+                (32, 0),
+                (57, 1),
+                (57, 0),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn encode_unicode_azerty() -> Result<()> {
+        let fr = Layout::builtin("fr")?;
+        let keys = key_events(&encode_on(
+            &[Input::Key(KeyInput::Unicode('é'))],
+            Some(&fr),
+        )?);
+        // ctrl+shift+u, e, shift+9 and space
+        assert_eq!(
+            keys[6..],
+            [
+                (18, 1),
+                (18, 0),
+                (42, 1),
+                (10, 1),
+                (10, 0),
+                (42, 0),
+                (57, 1),
+                (57, 0),
+            ]
+        );
+
+        // no digits on the layout
+        let letters = Layout::parse("letters", "A a A\nU u U\nSpace U+0020 U+0020")?;
+        assert!(encode_on(&[Input::Key(KeyInput::Unicode('é'))], Some(&letters)).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn encode_wheels() -> Result<()> {
+        let events = encode_all(&[
+            Input::Mouse(MouseInput::Wheel(-240)),
+            Input::Mouse(MouseInput::HWheel(30)),
+        ])?;
+        assert_eq!(
+            events,
+            vec![
+                Event::new(EV_REL, REL_WHEEL_HI_RES, -240),
+                Event::new(EV_REL, REL_WHEEL, -2),
+                Event::syn(),
+                Event::new(EV_REL, REL_HWHEEL_HI_RES, 30),
+                Event::new(EV_REL, REL_HWHEEL, 1),
+                Event::syn(),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn encode_click() -> Result<()> {
+        let events = encode_all(&[Input::Mouse(MouseInput::Press(
+            MouseButton::ButtonX1,
+            PressType::Click,
+        ))])?;
+        assert_eq!(
+            events,
+            vec![
+                Event::new(EV_KEY, BTN_SIDE, 1),
+                Event::syn(),
+                Event::new(EV_KEY, BTN_SIDE, 0),
+                Event::syn(),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn write_to_pipe() -> Result<()> {
+        let mut fds = [0; 2];
+        assert_eq!(0, unsafe { libc::pipe(fds.as_mut_ptr()) });
+        let events = encode_all(&[Input::Mouse(MouseInput::MoveDelta(3, -4))])?;
+        write_events(fds[1], &events)?;
+
+        let mut records: [libc::input_event; 3] = unsafe { std::mem::zeroed() };
+        let size = std::mem::size_of_val(&records);
+        let read = unsafe { libc::read(fds[0], records.as_mut_ptr() as *mut libc::c_void, size) };
+        unsafe {
+            libc::close(fds[0]);
+            libc::close(fds[1]);
+        }
+        assert_eq!(size as isize, read);
+        let read_back: Vec<Event> = records
+            .iter()
+            .map(|r| Event::new(r.type_, r.code, r.value))
+            .collect();
+        assert_eq!(events, read_back);
+        Ok(())
+    }
+}
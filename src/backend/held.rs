@@ -0,0 +1,196 @@
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+#[cfg(target_os = "linux")]
+use std::{io::Write, panic, sync::TryLockError};
+
+use super::{Input, InputBackend};
+use crate::{
+    errors::Result,
+    keyboard::KeyInput,
+    mouse::{MouseInput, PressType},
+};
+
+lazy_static! {
+    static ref HELD: Mutex<Held> = Mutex::new(Held::default());
+}
+
+/// Keys and buttons injected down and not released yet, with their backend
+#[derive(Default)]
+struct Held {
+    entries: Vec<(Arc<dyn InputBackend>, Input)>,
+}
+
+// the release of a press, None when `input` presses nothing
+fn release_of(input: &Input) -> Option<Input> {
+    match input {
+        Input::Key(KeyInput::KeyDown(key)) => Some(Input::Key(KeyInput::KeyUp(*key))),
+        Input::Key(KeyInput::ScanCodeDown(code)) => Some(Input::Key(KeyInput::ScanCodeUp(*code))),
+        Input::Mouse(MouseInput::Press(button, PressType::PressDown)) => {
+            Some(Input::Mouse(MouseInput::Press(*button, PressType::PressUp)))
+        }
+        _ => None,
+    }
+}
+
+impl Held {
+    fn track(&mut self, backend: &Arc<dyn InputBackend>, inputs: &[Input]) {
+        for input in inputs {
+            if let Some(release) = release_of(input) {
+                if !self.entries.iter().any(|(_, held)| *held == release) {
+                    self.entries.push((backend.clone(), release));
+                }
+            } else {
+                // a release of the same key by any backend counts
+                self.entries.retain(|(_, held)| held != input);
+            }
+        }
+    }
+
+    // the releases to send, grouped by backend, last pressed first, only
+    // those of backends passing `filter`
+    fn take(
+        &mut self,
+        filter: impl Fn(&dyn InputBackend) -> bool,
+    ) -> Vec<(Arc<dyn InputBackend>, Vec<Input>)> {
+        let mut batches: Vec<(Arc<dyn InputBackend>, Vec<Input>)> = Vec::new();
+        let (taken, kept) = self
+            .entries
+            .drain(..)
+            .partition(|(backend, _)| filter(backend.as_ref()));
+        self.entries = kept;
+        for (backend, release) in taken.into_iter().rev() {
+            match batches.last_mut() {
+                Some((last, releases)) if Arc::ptr_eq(last, &backend) => releases.push(release),
+                _ => batches.push((backend, vec![release])),
+            }
+        }
+        batches
+    }
+}
+
+// a panic while tracking leaves the list usable
+fn held() -> MutexGuard<'static, Held> {
+    HELD.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Remember what `inputs` pressed or released through `backend`
+pub(super) fn track(backend: &Arc<dyn InputBackend>, inputs: &[Input]) {
+    held().track(backend, inputs);
+}
+
+fn send_releases(batches: Vec<(Arc<dyn InputBackend>, Vec<Input>)>) -> Result<()> {
+    for (backend, releases) in batches {
+        backend.input(&releases)?;
+    }
+    Ok(())
+}
+
+/// Release the keys and mouse buttons injected down and still held, each
+/// through the backend which pressed it
+pub fn release_held() -> Result<()> {
+    let batches = held().take(|_| true);
+    send_releases(batches)
+}
+
+// at unload, never wait for a thread holding the list, and skip backends
+// which cannot send then, nothing may unwind into the caller either. Linux
+// only, windows forbids sending input from DllMain under the loader lock.
+#[cfg(target_os = "linux")]
+extern "C" fn release_on_unload() {
+    let _ = panic::catch_unwind(|| {
+        let mut held = match HELD.try_lock() {
+            Ok(held) => held,
+            Err(TryLockError::Poisoned(e)) => e.into_inner(),
+            Err(TryLockError::WouldBlock) => return,
+        };
+        let batches = held.take(|backend| backend.sends_at_unload());
+        drop(held);
+        if let Err(e) = send_releases(batches) {
+            let _ = writeln!(std::io::stderr(), "{}", e);
+        }
+    });
+}
+
+// run at dlclose() and process exit
+#[cfg(target_os = "linux")]
+#[used]
+#[link_section = ".fini_array"]
+static RELEASE_ON_UNLOAD: extern "C" fn() = release_on_unload;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{backend::RecordingBackend, key::Key, mouse::MouseButton};
+
+    #[test]
+    fn release_still_held() {
+        let first: Arc<dyn InputBackend> = Arc::new(RecordingBackend::new());
+        let second: Arc<dyn InputBackend> = Arc::new(RecordingBackend::new());
+        let mut held = Held::default();
+        held.track(
+            &first,
+            &[
+                Input::Key(KeyInput::KeyDown(Key::Ctrl)),
+                Input::Key(KeyInput::KeyDown(Key::A)),
+                Input::Key(KeyInput::KeyUp(Key::A)),
+                Input::Key(KeyInput::KeyDown(Key::Shift)),
+            ],
+        );
+        held.track(
+            &second,
+            &[
+                Input::Mouse(MouseInput::Press(
+                    MouseButton::ButtonLeft,
+                    PressType::PressDown,
+                )),
+                Input::Key(KeyInput::KeyDown(Key::LWin)),
+                Input::Key(KeyInput::KeyUp(Key::Shift)),
+            ],
+        );
+
+        let batches = held.take(|_| true);
+        assert_eq!(2, batches.len());
+        assert!(Arc::ptr_eq(&second, &batches[0].0));
+        assert_eq!(
+            batches[0].1,
+            vec![
+                Input::Key(KeyInput::KeyUp(Key::LWin)),
+                Input::Mouse(MouseInput::Press(
+                    MouseButton::ButtonLeft,
+                    PressType::PressUp
+                )),
+            ]
+        );
+        assert!(Arc::ptr_eq(&first, &batches[1].0));
+        assert_eq!(batches[1].1, vec![Input::Key(KeyInput::KeyUp(Key::Ctrl))]);
+        assert!(held.take(|_| true).is_empty());
+    }
+
+    // like the X11 backend
+    struct LateBackend;
+
+    impl InputBackend for LateBackend {
+        fn input(&self, _inputs: &[Input]) -> Result<()> {
+            Ok(())
+        }
+
+        fn sends_at_unload(&self) -> bool {
+            false
+        }
+    }
+
+    #[test]
+    fn skip_at_unload() {
+        let late: Arc<dyn InputBackend> = Arc::new(LateBackend);
+        let rec: Arc<dyn InputBackend> = Arc::new(RecordingBackend::new());
+        let mut held = Held::default();
+        held.track(&late, &[Input::Key(KeyInput::KeyDown(Key::Ctrl))]);
+        held.track(&rec, &[Input::Key(KeyInput::KeyDown(Key::A))]);
+
+        let batches = held.take(|backend| backend.sends_at_unload());
+        assert_eq!(1, batches.len());
+        assert!(Arc::ptr_eq(&rec, &batches[0].0));
+        // still released by `release_held`
+        let batches = held.take(|_| true);
+        assert!(Arc::ptr_eq(&late, &batches[0].0));
+    }
+}
@@ -0,0 +1,271 @@
+use std::{
+    cell::RefCell,
+    sync::{Arc, RwLock},
+    thread,
+};
+
+use crate::{errors::Result, keyboard::KeyInput, mouse::MouseInput};
+
+#[cfg(target_os = "linux")]
+mod evdev;
+mod held;
+mod print;
+mod recording;
+mod timeline;
+#[cfg(target_os = "linux")]
+mod uinput;
+#[cfg(windows)]
+mod win32;
+#[cfg(target_os = "linux")]
+mod x11;
+
+pub use held::release_held;
+pub use print::PrintBackend;
+pub use recording::RecordingBackend;
+pub use timeline::{Played, Timeline};
+#[cfg(target_os = "linux")]
+pub use uinput::UinputBackend;
+#[cfg(windows)]
+pub use win32::Win32Backend;
+#[cfg(target_os = "linux")]
+pub use x11::X11Backend;
+
+/// One injected event, keyboard or mouse
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Input {
+    Key(KeyInput),
+    Mouse(MouseInput),
+}
+
+/// Something able to inject input events.
+///
+/// `keyboard::input_keys` and `mouse::input_mouses` end up here, the inputs
+/// of one call are handed over as one batch and should be sent in order.
+/// Batches never contain `KeyInput::Delay`, `input` waits between them.
+pub trait InputBackend: Send + Sync {
+    fn input(&self, inputs: &[Input]) -> Result<()>;
+
+    /// Whether inputs can be sent while the library is unloaded, false when
+    /// the backend uses a library which may already be torn down then
+    fn sends_at_unload(&self) -> bool {
+        true
+    }
+
+    /// The cursor position, where mouse paths start
+    fn cursor_pos(&self) -> Result<(i32, i32)> {
+        bail!("the cursor position is not available with this backend")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BackendKind {
+    Win32 = 0,
+    Recording = 1,
+    Print = 2,
+    X11 = 3,
+    Uinput = 4,
+}
+
+impl BackendKind {
+    pub fn from_i32(i: i32) -> Option<BackendKind> {
+        match i {
+            0 => Some(BackendKind::Win32),
+            1 => Some(BackendKind::Recording),
+            2 => Some(BackendKind::Print),
+            3 => Some(BackendKind::X11),
+            4 => Some(BackendKind::Uinput),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(windows)]
+fn default_backend() -> Arc<dyn InputBackend> {
+    Arc::new(Win32Backend)
+}
+
+#[cfg(not(windows))]
+fn default_backend() -> Arc<dyn InputBackend> {
+    Arc::new(PrintBackend)
+}
+
+lazy_static! {
+    static ref BACKEND: RwLock<Arc<dyn InputBackend>> = RwLock::new(default_backend());
+}
+
+thread_local! {
+    static SCOPED: RefCell<Vec<Arc<dyn InputBackend>>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Create a new backend of `kind`
+pub fn create(kind: BackendKind) -> Result<Arc<dyn InputBackend>> {
+    Ok(match kind {
+        #[cfg(windows)]
+        BackendKind::Win32 => Arc::new(Win32Backend),
+        #[cfg(not(windows))]
+        BackendKind::Win32 => bail!("Win32 backend is only available on windows"),
+        BackendKind::Recording => Arc::new(RecordingBackend::new()),
+        BackendKind::Print => Arc::new(PrintBackend),
+        #[cfg(target_os = "linux")]
+        BackendKind::X11 => Arc::new(X11Backend::open()?),
+        #[cfg(not(target_os = "linux"))]
+        BackendKind::X11 => bail!("X11 backend is only available on linux"),
+        #[cfg(target_os = "linux")]
+        BackendKind::Uinput => Arc::new(UinputBackend::open()?),
+        #[cfg(not(target_os = "linux"))]
+        BackendKind::Uinput => bail!("uinput backend is only available on linux"),
+    })
+}
+
+/// Replace the process wide backend
+pub fn set_backend(backend: Arc<dyn InputBackend>) {
+    *BACKEND.write().unwrap() = backend;
+}
+
+/// Replace the process wide backend with a new backend of `kind`
+pub fn select(kind: BackendKind) -> Result<()> {
+    set_backend(create(kind)?);
+    Ok(())
+}
+
+/// Replace the process wide backend with a uinput backend mapping absolute
+/// moves to a `width` x `height` screen
+#[cfg(target_os = "linux")]
+pub fn select_uinput(width: i32, height: i32) -> Result<()> {
+    set_backend(Arc::new(UinputBackend::with_screen_size(width, height)?));
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn select_uinput(_width: i32, _height: i32) -> Result<()> {
+    bail!("uinput backend is only available on linux")
+}
+
+/// Backend used by the current thread
+pub fn current() -> Arc<dyn InputBackend> {
+    if let Some(backend) = SCOPED.with(|s| s.borrow().last().cloned()) {
+        return backend;
+    }
+    BACKEND.read().unwrap().clone()
+}
+
+struct ScopeGuard;
+
+impl Drop for ScopeGuard {
+    fn drop(&mut self) {
+        SCOPED.with(|s| s.borrow_mut().pop());
+    }
+}
+
+/// Run `f` with `backend` used by the current thread only, the process wide
+/// backend is untouched. Handy for tests running in parallel.
+pub fn with_backend<T>(backend: Arc<dyn InputBackend>, f: impl FnOnce() -> T) -> T {
+    SCOPED.with(|s| s.borrow_mut().push(backend));
+    let _guard = ScopeGuard;
+    f()
+}
+
+fn delay(input: &Input) -> Option<std::time::Duration> {
+    match input {
+        Input::Key(KeyInput::Delay(duration)) => Some(*duration),
+        _ => None,
+    }
+}
+
+// send one batch, keeping track of what is left down
+fn send(backend: &Arc<dyn InputBackend>, inputs: &[Input]) -> Result<()> {
+    backend.input(inputs)?;
+    held::track(backend, inputs);
+    Ok(())
+}
+
+/// Send inputs with the current backend, split into batches at delays.
+/// Delays are slept, see `Timeline` for inputs at precise offsets.
+///
+/// Keys and buttons left down are released by `release_held`, which also
+/// runs when the library is unloaded on linux.
+pub fn input(inputs: &[Input]) -> Result<()> {
+    let backend = current();
+    let mut rest = inputs;
+    while let Some(pos) = rest.iter().position(|i| delay(i).is_some()) {
+        if pos > 0 {
+            send(&backend, &rest[..pos])?;
+        }
+        thread::sleep(delay(&rest[pos]).unwrap());
+        rest = &rest[pos + 1..];
+    }
+    if !rest.is_empty() || inputs.is_empty() {
+        send(&backend, rest)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mouse::{MouseButton, PressType};
+
+    #[test]
+    fn scoped_backend() -> Result<()> {
+        let rec = Arc::new(RecordingBackend::new());
+        with_backend(rec.clone(), || {
+            input(&[Input::Mouse(MouseInput::Press(
+                MouseButton::ButtonLeft,
+                PressType::Click,
+            ))])
+        })?;
+        assert_eq!(
+            rec.take(),
+            vec![Input::Mouse(MouseInput::Press(
+                MouseButton::ButtonLeft,
+                PressType::Click
+            ))]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn nested_scoped_backend() -> Result<()> {
+        let outer = Arc::new(RecordingBackend::new());
+        let inner = Arc::new(RecordingBackend::new());
+        with_backend(outer.clone(), || -> Result<()> {
+            with_backend(inner.clone(), || {
+                input(&[Input::Mouse(MouseInput::Wheel(1))])
+            })?;
+            input(&[Input::Mouse(MouseInput::Wheel(2))])
+        })?;
+        assert_eq!(inner.take(), vec![Input::Mouse(MouseInput::Wheel(1))]);
+        assert_eq!(outer.take(), vec![Input::Mouse(MouseInput::Wheel(2))]);
+        Ok(())
+    }
+
+    #[derive(Default)]
+    struct Batches(std::sync::Mutex<Vec<usize>>);
+
+    impl InputBackend for Batches {
+        fn input(&self, inputs: &[Input]) -> Result<()> {
+            self.0.lock().unwrap().push(inputs.len());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn split_at_delays() -> Result<()> {
+        let batches = Arc::new(Batches::default());
+        let wheel = Input::Mouse(MouseInput::Wheel(1));
+        let delay = Input::Key(KeyInput::Delay(std::time::Duration::from_millis(1)));
+        with_backend(batches.clone(), || {
+            input(&[
+                wheel, wheel, delay, wheel, delay, delay, wheel, wheel, wheel,
+            ])
+        })?;
+        assert_eq!(*batches.0.lock().unwrap(), vec![2, 1, 3]);
+        Ok(())
+    }
+
+    #[test]
+    fn backend_kind_from_i32() {
+        assert_eq!(Some(BackendKind::Print), BackendKind::from_i32(2));
+        assert_eq!(None, BackendKind::from_i32(42));
+    }
+}
@@ -0,0 +1,14 @@
+use super::{Input, InputBackend};
+use crate::errors::Result;
+
+/// Print every input to stdout instead of sending it.
+pub struct PrintBackend;
+
+impl InputBackend for PrintBackend {
+    fn input(&self, inputs: &[Input]) -> Result<()> {
+        for input in inputs {
+            println!("{:?}", input);
+        }
+        Ok(())
+    }
+}
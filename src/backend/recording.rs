@@ -0,0 +1,57 @@
+use std::sync::Mutex;
+
+use super::{Input, InputBackend};
+use crate::{errors::Result, mouse::MouseInput};
+
+/// Keep every input in memory instead of sending it, for tests and dry runs.
+#[derive(Default)]
+pub struct RecordingBackend {
+    inputs: Mutex<Vec<Input>>,
+    // None until set, then moved by the recorded moves
+    cursor: Mutex<Option<(i32, i32)>>,
+}
+
+impl RecordingBackend {
+    pub fn new() -> RecordingBackend {
+        RecordingBackend::default()
+    }
+
+    /// Copy of inputs recorded so far
+    pub fn inputs(&self) -> Vec<Input> {
+        self.inputs.lock().unwrap().clone()
+    }
+
+    /// Take inputs recorded so far, leaving the record empty
+    pub fn take(&self) -> Vec<Input> {
+        std::mem::take(&mut *self.inputs.lock().unwrap())
+    }
+
+    /// Set the cursor position returned by `cursor_pos`
+    pub fn set_cursor_pos(&self, x: i32, y: i32) {
+        *self.cursor.lock().unwrap() = Some((x, y));
+    }
+}
+
+impl InputBackend for RecordingBackend {
+    fn input(&self, inputs: &[Input]) -> Result<()> {
+        let mut cursor = self.cursor.lock().unwrap();
+        for input in inputs {
+            match (input, cursor.as_mut()) {
+                (Input::Mouse(MouseInput::MoveTo(x, y)), Some(pos)) => *pos = (*x, *y),
+                (Input::Mouse(MouseInput::MoveDelta(x, y)), Some(pos)) => {
+                    *pos = (pos.0 + x, pos.1 + y)
+                }
+                _ => {}
+            }
+        }
+        self.inputs.lock().unwrap().extend_from_slice(inputs);
+        Ok(())
+    }
+
+    fn cursor_pos(&self) -> Result<(i32, i32)> {
+        match *self.cursor.lock().unwrap() {
+            Some(pos) => Ok(pos),
+            None => bail!("no cursor position set"),
+        }
+    }
+}
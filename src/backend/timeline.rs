@@ -0,0 +1,207 @@
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+use super::{current, delay, send, Input};
+use crate::errors::Result;
+
+// left to spin before a deadline, sleeping is not precise enough for the
+// last bit, about a timer tick on windows
+#[cfg(windows)]
+const SPIN: Duration = Duration::from_millis(16);
+#[cfg(not(windows))]
+const SPIN: Duration = Duration::from_millis(1);
+
+/// Inputs at offsets from the start, like ctrl down at 0ms and c down at 35ms.
+///
+/// `play` waits for each offset from the start instead of sleeping between
+/// inputs, so waits do not add up their overshoot.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Timeline {
+    // sorted by offset, in the order added for the same offset
+    events: Vec<(Duration, Input)>,
+    end: Duration,
+}
+
+/// An input played by `Timeline::play`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Played {
+    pub input: Input,
+    /// offset in the timeline
+    pub intended: Duration,
+    /// offset from the start of `play` when it was sent
+    pub actual: Duration,
+}
+
+impl Played {
+    /// How much later than intended it was sent
+    pub fn late(&self) -> Duration {
+        self.actual.saturating_sub(self.intended)
+    }
+}
+
+impl Timeline {
+    pub fn new() -> Timeline {
+        Timeline::default()
+    }
+
+    /// The inputs with their delays turned into offsets, a delay at the end
+    /// is kept as a wait
+    pub fn from_inputs(inputs: &[Input]) -> Timeline {
+        let mut timeline = Timeline::new();
+        let mut offset = Duration::ZERO;
+        for input in inputs {
+            match delay(input) {
+                Some(duration) => offset += duration,
+                None => {
+                    timeline.at(offset, *input);
+                }
+            }
+        }
+        timeline.wait_until(offset);
+        timeline
+    }
+
+    /// Add `input` at `offset`, after the inputs already at `offset`. A
+    /// `KeyInput::Delay` is a wait from `offset`.
+    pub fn at(&mut self, offset: Duration, input: Input) -> &mut Timeline {
+        if let Some(duration) = delay(&input) {
+            return self.wait_until(offset + duration);
+        }
+        let pos = self.events.partition_point(|(at, _)| *at <= offset);
+        self.events.insert(pos, (offset, input));
+        self.wait_until(offset)
+    }
+
+    /// Make `play` last until `offset` at least
+    pub fn wait_until(&mut self, offset: Duration) -> &mut Timeline {
+        self.end = self.end.max(offset);
+        self
+    }
+
+    /// The inputs and their offsets, in the order played
+    pub fn events(&self) -> &[(Duration, Input)] {
+        &self.events
+    }
+
+    /// Time from the start to the last input or wait
+    pub fn duration(&self) -> Duration {
+        self.end
+    }
+
+    /// Send every input at its offset with the current backend, inputs at the
+    /// same offset as one batch. Return when each input was actually sent.
+    pub fn play(&self) -> Result<Vec<Played>> {
+        let backend = current();
+        let start = Instant::now();
+        let mut played = Vec::with_capacity(self.events.len());
+        let mut rest = &self.events[..];
+        while let Some(&(offset, _)) = rest.first() {
+            let count = rest.iter().take_while(|(at, _)| *at == offset).count();
+            let batch: Vec<Input> = rest[..count].iter().map(|(_, input)| *input).collect();
+            wait_until(start + offset);
+            let actual = start.elapsed();
+            send(&backend, &batch)?;
+            played.extend(batch.into_iter().map(|input| Played {
+                input,
+                intended: offset,
+                actual,
+            }));
+            rest = &rest[count..];
+        }
+        wait_until(start + self.end);
+        Ok(played)
+    }
+}
+
+// sleep until a bit before `deadline`, then spin
+fn wait_until(deadline: Instant) {
+    loop {
+        let now = Instant::now();
+        if now >= deadline {
+            return;
+        }
+        let left = deadline - now;
+        if left > SPIN {
+            thread::sleep(left - SPIN);
+        } else {
+            std::hint::spin_loop();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::{
+        backend::{with_backend, RecordingBackend},
+        key::Key,
+        keyboard::KeyInput,
+    };
+
+    #[test]
+    fn order() {
+        let ms = Duration::from_millis;
+        let key = |k: KeyInput| Input::Key(k);
+        let inputs = [
+            key(KeyInput::KeyDown(Key::Ctrl)),
+            key(KeyInput::Delay(ms(35))),
+            key(KeyInput::KeyDown(Key::C)),
+            key(KeyInput::KeyUp(Key::C)),
+            key(KeyInput::Delay(ms(10))),
+            key(KeyInput::KeyUp(Key::Ctrl)),
+            key(KeyInput::Delay(ms(5))),
+        ];
+        let timeline = Timeline::from_inputs(&inputs);
+        assert_eq!(
+            timeline.events(),
+            &[
+                (ms(0), inputs[0]),
+                (ms(35), inputs[2]),
+                (ms(35), inputs[3]),
+                (ms(45), inputs[5]),
+            ]
+        );
+        assert_eq!(ms(50), timeline.duration());
+
+        let mut built = Timeline::new();
+        built
+            .at(ms(45), inputs[5])
+            .at(ms(35), inputs[2])
+            .at(ms(0), inputs[0])
+            .at(ms(35), inputs[3])
+            .at(ms(45), inputs[6]);
+        assert_eq!(timeline, built);
+    }
+
+    #[test]
+    fn play() -> Result<()> {
+        let ms = Duration::from_millis;
+        let mut timeline = Timeline::new();
+        for (i, key) in [Key::A, Key::B, Key::C].into_iter().enumerate() {
+            let offset = ms(10 * i as u64);
+            timeline
+                .at(offset, Input::Key(KeyInput::KeyDown(key)))
+                .at(offset + ms(3), Input::Key(KeyInput::KeyUp(key)));
+        }
+        timeline.wait_until(ms(40));
+
+        let rec = Arc::new(RecordingBackend::new());
+        let start = Instant::now();
+        let played = with_backend(rec.clone(), || timeline.play())?;
+        assert!(start.elapsed() >= ms(40));
+        let inputs: Vec<Input> = timeline.events().iter().map(|(_, input)| *input).collect();
+        assert_eq!(inputs, rec.inputs());
+        assert_eq!(6, played.len());
+        for (p, (offset, input)) in played.iter().zip(timeline.events()) {
+            assert_eq!((*offset, *input), (p.intended, p.input));
+            assert!(p.actual >= p.intended);
+            // generous, the test may share the machine with others
+            assert!(p.late() < ms(50), "{:?}", p);
+        }
+        Ok(())
+    }
+}
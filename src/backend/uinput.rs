@@ -0,0 +1,210 @@
+use std::{
+    os::{raw::c_ulong, unix::io::RawFd},
+    sync::Mutex,
+    thread,
+    time::Duration,
+};
+
+use super::{
+    evdev::{self, Event},
+    Input, InputBackend,
+};
+use crate::{
+    errors::{Error, Result},
+    key::Key,
+    keyboard,
+    mouse::{MouseButton, MouseInput},
+};
+
+// ioctl requests from linux/uinput.h
+const UI_DEV_CREATE: c_ulong = 0x5501;
+const UI_DEV_DESTROY: c_ulong = 0x5502;
+const UI_DEV_SETUP: c_ulong = 0x405C5503;
+const UI_ABS_SETUP: c_ulong = 0x401C5504;
+const UI_SET_EVBIT: c_ulong = 0x40045564;
+const UI_SET_KEYBIT: c_ulong = 0x40045565;
+const UI_SET_RELBIT: c_ulong = 0x40045566;
+const UI_SET_ABSBIT: c_ulong = 0x40045567;
+
+const BUS_VIRTUAL: u16 = 0x06;
+
+// for udev and the display server to pick up new devices, events sent
+// before are lost
+const CREATE_WAIT: Duration = Duration::from_millis(200);
+
+/// Screen size assumed by `UinputBackend::open`
+pub const DEFAULT_SCREEN_SIZE: (i32, i32) = (1920, 1080);
+
+struct Device {
+    fd: RawFd,
+}
+
+fn ioctl_failed(request: &str) -> Error {
+    Error::with_chain(
+        std::io::Error::last_os_error(),
+        format!("ioctl({}) failed", request),
+    )
+}
+
+impl Device {
+    fn open() -> Result<Device> {
+        let fd = unsafe { libc::open(c"/dev/uinput".as_ptr(), libc::O_WRONLY | libc::O_NONBLOCK) };
+        if fd < 0 {
+            bail!(Error::with_chain(
+                std::io::Error::last_os_error(),
+                "open(/dev/uinput) failed"
+            ));
+        }
+        Ok(Device { fd })
+    }
+
+    fn set_bit(&self, request: c_ulong, name: &str, bit: u16) -> Result<()> {
+        if unsafe { libc::ioctl(self.fd, request, bit as libc::c_int) } < 0 {
+            bail!(ioctl_failed(name));
+        }
+        Ok(())
+    }
+
+    fn set_abs(&self, code: u16, max: i32) -> Result<()> {
+        let mut setup: libc::uinput_abs_setup = unsafe { std::mem::zeroed() };
+        setup.code = code;
+        setup.absinfo.maximum = max;
+        if unsafe { libc::ioctl(self.fd, UI_ABS_SETUP, &setup) } < 0 {
+            bail!(ioctl_failed("UI_ABS_SETUP"));
+        }
+        Ok(())
+    }
+
+    fn create(&self, name: &str, product: u16) -> Result<()> {
+        let mut setup: libc::uinput_setup = unsafe { std::mem::zeroed() };
+        setup.id.bustype = BUS_VIRTUAL;
+        setup.id.vendor = 0x1209;
+        setup.id.product = product;
+        for (dst, src) in setup.name.iter_mut().zip(name.bytes()) {
+            *dst = src as libc::c_char;
+        }
+        if unsafe { libc::ioctl(self.fd, UI_DEV_SETUP, &setup) } < 0 {
+            bail!(ioctl_failed("UI_DEV_SETUP"));
+        }
+        if unsafe { libc::ioctl(self.fd, UI_DEV_CREATE) } < 0 {
+            bail!(ioctl_failed("UI_DEV_CREATE"));
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Device {
+    fn drop(&mut self) {
+        unsafe {
+            libc::ioctl(self.fd, UI_DEV_DESTROY);
+            libc::close(self.fd);
+        }
+    }
+}
+
+const BUTTONS: [MouseButton; 5] = [
+    MouseButton::ButtonLeft,
+    MouseButton::ButtonRight,
+    MouseButton::ButtonMiddle,
+    MouseButton::ButtonX1,
+    MouseButton::ButtonX2,
+];
+
+/// Send inputs through virtual devices created with `/dev/uinput`, works
+/// under X11, Wayland and the console.
+///
+/// Two devices are created: a keyboard with a relative mouse, and an absolute
+/// pointer for `MouseInput::MoveTo`, whose axes span the given screen size.
+///
+/// The cursor cannot be read back, so `mouse::move_to_with` starts from the
+/// last `MoveTo` and fails before one, or after a relative move.
+pub struct UinputBackend {
+    relative: Device,
+    absolute: Device,
+    // the last `MoveTo`, None before it and after a relative move
+    cursor: Mutex<Option<(i32, i32)>>,
+}
+
+impl UinputBackend {
+    /// Create the devices assuming `DEFAULT_SCREEN_SIZE`
+    pub fn open() -> Result<UinputBackend> {
+        UinputBackend::with_screen_size(DEFAULT_SCREEN_SIZE.0, DEFAULT_SCREEN_SIZE.1)
+    }
+
+    /// Create the devices, absolute moves are mapped to a `width` x `height` screen
+    pub fn with_screen_size(width: i32, height: i32) -> Result<UinputBackend> {
+        if width <= 0 || height <= 0 {
+            bail!("invalid screen size {}x{}", width, height);
+        }
+        let relative = Device::open()?;
+        relative.set_bit(UI_SET_EVBIT, "UI_SET_EVBIT", evdev::EV_KEY)?;
+        relative.set_bit(UI_SET_EVBIT, "UI_SET_EVBIT", evdev::EV_REL)?;
+        for code in Key::all().filter_map(Key::to_evdev) {
+            relative.set_bit(UI_SET_KEYBIT, "UI_SET_KEYBIT", code)?;
+        }
+        for button in BUTTONS.iter() {
+            relative.set_bit(UI_SET_KEYBIT, "UI_SET_KEYBIT", evdev::button_code(button))?;
+        }
+        for code in [
+            evdev::REL_X,
+            evdev::REL_Y,
+            evdev::REL_WHEEL,
+            evdev::REL_HWHEEL,
+            evdev::REL_WHEEL_HI_RES,
+            evdev::REL_HWHEEL_HI_RES,
+        ] {
+            relative.set_bit(UI_SET_RELBIT, "UI_SET_RELBIT", code)?;
+        }
+        relative.create("hotkeyz virtual input", 1)?;
+
+        let absolute = Device::open()?;
+        absolute.set_bit(UI_SET_EVBIT, "UI_SET_EVBIT", evdev::EV_KEY)?;
+        absolute.set_bit(UI_SET_EVBIT, "UI_SET_EVBIT", evdev::EV_ABS)?;
+        absolute.set_bit(UI_SET_KEYBIT, "UI_SET_KEYBIT", evdev::BTN_LEFT)?;
+        absolute.set_bit(UI_SET_ABSBIT, "UI_SET_ABSBIT", evdev::ABS_X)?;
+        absolute.set_bit(UI_SET_ABSBIT, "UI_SET_ABSBIT", evdev::ABS_Y)?;
+        absolute.set_abs(evdev::ABS_X, width - 1)?;
+        absolute.set_abs(evdev::ABS_Y, height - 1)?;
+        absolute.create("hotkeyz virtual pointer", 2)?;
+        thread::sleep(CREATE_WAIT);
+
+        Ok(UinputBackend {
+            relative,
+            absolute,
+            cursor: Mutex::new(None),
+        })
+    }
+}
+
+impl InputBackend for UinputBackend {
+    fn input(&self, inputs: &[Input]) -> Result<()> {
+        let layout = keyboard::layout();
+        let mut events: Vec<Event> = Vec::new();
+        for input in inputs {
+            let fd = match input {
+                Input::Mouse(MouseInput::MoveTo(_, _)) => self.absolute.fd,
+                _ => self.relative.fd,
+            };
+            evdev::encode(input, layout.as_deref(), &mut events)?;
+            evdev::write_events(fd, &events)?;
+            events.clear();
+            match input {
+                Input::Mouse(MouseInput::MoveTo(x, y)) => {
+                    *self.cursor.lock().unwrap() = Some((*x, *y))
+                }
+                // moved by pointer acceleration, nowhere known
+                Input::Mouse(MouseInput::MoveDelta(_, _)) => *self.cursor.lock().unwrap() = None,
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    // uinput cannot read the cursor, only where it was moved to last
+    fn cursor_pos(&self) -> Result<(i32, i32)> {
+        match *self.cursor.lock().unwrap() {
+            Some(pos) => Ok(pos),
+            None => bail!("the cursor position is unknown with uinput before a move to a position"),
+        }
+    }
+}
@@ -0,0 +1,193 @@
+use windows::Win32::{
+    Foundation::POINT,
+    UI::{
+        Input::KeyboardAndMouse::{
+            SendInput, INPUT, INPUT_KEYBOARD, INPUT_MOUSE, KEYBD_EVENT_FLAGS,
+            KEYEVENTF_EXTENDEDKEY, KEYEVENTF_KEYUP, KEYEVENTF_SCANCODE, KEYEVENTF_UNICODE,
+            MOUSEEVENTF_ABSOLUTE, MOUSEEVENTF_HWHEEL, MOUSEEVENTF_LEFTDOWN, MOUSEEVENTF_LEFTUP,
+            MOUSEEVENTF_MIDDLEDOWN, MOUSEEVENTF_MIDDLEUP, MOUSEEVENTF_MOVE, MOUSEEVENTF_RIGHTDOWN,
+            MOUSEEVENTF_RIGHTUP, MOUSEEVENTF_WHEEL, MOUSEEVENTF_XDOWN, MOUSEEVENTF_XUP,
+            VIRTUAL_KEY,
+        },
+        WindowsAndMessaging::{
+            GetCursorPos, GetSystemMetrics, SM_CXSCREEN, SM_CYSCREEN, XBUTTON1, XBUTTON2,
+        },
+    },
+};
+
+use super::{Input, InputBackend};
+use crate::{
+    errors::{Error, Result},
+    keyboard::KeyInput,
+    mouse::{MouseButton, MouseInput, PressType},
+};
+
+/// Send inputs with `SendInput`
+pub struct Win32Backend;
+
+static mut SCREEN_SIZE: (i32, i32) = (0, 0);
+static INIT: std::sync::Once = std::sync::Once::new();
+
+fn screen_size() -> (i32, i32) {
+    unsafe {
+        INIT.call_once(|| {
+            SCREEN_SIZE.0 = GetSystemMetrics(SM_CXSCREEN);
+            SCREEN_SIZE.1 = GetSystemMetrics(SM_CYSCREEN);
+        });
+        SCREEN_SIZE
+    }
+}
+
+// the low byte of a scan code and its flags, extended keys are 0xE0xx
+fn scancode_flags(scancode: u16) -> (u16, KEYBD_EVENT_FLAGS) {
+    if scancode >> 8 == 0xE0 {
+        (scancode & 0xFF, KEYEVENTF_EXTENDEDKEY)
+    } else {
+        (scancode & 0xFF, KEYBD_EVENT_FLAGS(0))
+    }
+}
+
+fn push_key(inputs: &mut Vec<INPUT>, key_input: &KeyInput) {
+    let mut input: INPUT = unsafe { std::mem::zeroed() };
+    input.r#type = INPUT_KEYBOARD;
+    let (key, flags) = match key_input {
+        KeyInput::KeyDown(key) => (*key, KEYBD_EVENT_FLAGS(0)),
+        KeyInput::KeyUp(key) => (*key, KEYEVENTF_KEYUP),
+        KeyInput::ScanCodeDown(code) | KeyInput::ScanCodeUp(code) => {
+            let (scan, mut flags) = scancode_flags(*code);
+            flags |= KEYEVENTF_SCANCODE;
+            if let KeyInput::ScanCodeUp(_) = key_input {
+                flags |= KEYEVENTF_KEYUP;
+            }
+            let ki = unsafe { &mut input.Anonymous.ki };
+            ki.wScan = scan;
+            ki.dwFlags = flags;
+            inputs.push(input);
+            return;
+        }
+        KeyInput::Unicode(c) => {
+            // one down and up per UTF-16 unit, surrogate pairs are two units
+            let mut units = [0u16; 2];
+            for unit in c.encode_utf16(&mut units) {
+                for flags in [KEYEVENTF_UNICODE, KEYEVENTF_UNICODE | KEYEVENTF_KEYUP] {
+                    let ki = unsafe { &mut input.Anonymous.ki };
+                    ki.wScan = *unit;
+                    ki.dwFlags = flags;
+                    inputs.push(input);
+                }
+            }
+            return;
+        }
+        // waited by `backend::input`
+        KeyInput::Delay(_) => return,
+    };
+    let ki = unsafe { &mut input.Anonymous.ki };
+    ki.wVk = VIRTUAL_KEY(key.to_vk() as u16);
+    ki.dwFlags = flags;
+    // arrows, right ctrl and alt and the like are extended keys, without the
+    // flag some apps read them as numpad keys
+    if let Some(code) = key.to_scancode() {
+        let (scan, extended) = scancode_flags(code);
+        ki.wScan = scan;
+        ki.dwFlags |= extended;
+    }
+    inputs.push(input);
+}
+
+fn push_mouse(inputs: &mut Vec<INPUT>, mouse_input: &MouseInput) {
+    let mut input: INPUT = unsafe { std::mem::zeroed() };
+    input.r#type = INPUT_MOUSE;
+    let mi = unsafe { &mut input.Anonymous.mi };
+    match mouse_input {
+        MouseInput::MoveTo(x, y) => {
+            let (w, h) = screen_size();
+            mi.dx = (*x as i64 * 65535 / w as i64) as _;
+            mi.dy = (*y as i64 * 65535 / h as i64) as _;
+            mi.dwFlags = MOUSEEVENTF_MOVE | MOUSEEVENTF_ABSOLUTE;
+        }
+        MouseInput::MoveDelta(x, y) => {
+            mi.dx = *x;
+            mi.dy = *y;
+            mi.dwFlags = MOUSEEVENTF_MOVE;
+        }
+        MouseInput::Press(button, press) => match button {
+            MouseButton::ButtonLeft => match press {
+                PressType::PressDown => mi.dwFlags = MOUSEEVENTF_LEFTDOWN,
+                PressType::PressUp => mi.dwFlags = MOUSEEVENTF_LEFTUP,
+                PressType::Click => mi.dwFlags = MOUSEEVENTF_LEFTDOWN | MOUSEEVENTF_LEFTUP,
+            },
+            MouseButton::ButtonRight => match press {
+                PressType::PressDown => mi.dwFlags = MOUSEEVENTF_RIGHTDOWN,
+                PressType::PressUp => mi.dwFlags = MOUSEEVENTF_RIGHTUP,
+                PressType::Click => mi.dwFlags = MOUSEEVENTF_RIGHTDOWN | MOUSEEVENTF_RIGHTUP,
+            },
+            MouseButton::ButtonMiddle => match press {
+                PressType::PressDown => mi.dwFlags = MOUSEEVENTF_MIDDLEDOWN,
+                PressType::PressUp => mi.dwFlags = MOUSEEVENTF_MIDDLEUP,
+                PressType::Click => mi.dwFlags = MOUSEEVENTF_MIDDLEDOWN | MOUSEEVENTF_MIDDLEUP,
+            },
+            MouseButton::ButtonX1 => {
+                match press {
+                    PressType::PressDown => mi.dwFlags = MOUSEEVENTF_XDOWN,
+                    PressType::PressUp => mi.dwFlags = MOUSEEVENTF_XUP,
+                    PressType::Click => mi.dwFlags = MOUSEEVENTF_XDOWN | MOUSEEVENTF_XUP,
+                }
+                mi.mouseData = XBUTTON1.0 as _;
+            }
+            MouseButton::ButtonX2 => {
+                match press {
+                    PressType::PressDown => mi.dwFlags = MOUSEEVENTF_XDOWN,
+                    PressType::PressUp => mi.dwFlags = MOUSEEVENTF_XUP,
+                    PressType::Click => mi.dwFlags = MOUSEEVENTF_XDOWN | MOUSEEVENTF_XUP,
+                }
+                mi.mouseData = XBUTTON2.0 as _;
+            }
+        },
+        MouseInput::Wheel(amount) => {
+            mi.mouseData = *amount;
+            mi.dwFlags = MOUSEEVENTF_WHEEL;
+        }
+        MouseInput::HWheel(amount) => {
+            mi.mouseData = *amount;
+            mi.dwFlags = MOUSEEVENTF_HWHEEL;
+        }
+    }
+    inputs.push(input);
+}
+
+impl InputBackend for Win32Backend {
+    fn input(&self, inputs: &[Input]) -> Result<()> {
+        let mut win_inputs: Vec<INPUT> = Vec::with_capacity(inputs.len());
+        for input in inputs {
+            match input {
+                Input::Key(key_input) => push_key(&mut win_inputs, key_input),
+                Input::Mouse(mouse_input) => push_mouse(&mut win_inputs, mouse_input),
+            }
+        }
+
+        let sent = unsafe { SendInput(&win_inputs, std::mem::size_of::<INPUT>() as _) };
+        if sent != win_inputs.len() as u32 {
+            bail!(Error::with_chain(
+                std::io::Error::last_os_error(),
+                "SendInput() failed."
+            ));
+        }
+        Ok(())
+    }
+
+    // SendInput is a user32 call, not allowed under the loader lock
+    fn sends_at_unload(&self) -> bool {
+        false
+    }
+
+    fn cursor_pos(&self) -> Result<(i32, i32)> {
+        let mut point = POINT::default();
+        if !unsafe { GetCursorPos(&mut point) }.as_bool() {
+            bail!(Error::with_chain(
+                std::io::Error::last_os_error(),
+                "GetCursorPos() failed"
+            ));
+        }
+        Ok((point.x, point.y))
+    }
+}
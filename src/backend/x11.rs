@@ -0,0 +1,477 @@
+use std::{
+    os::raw::{c_uint, c_ulong},
+    sync::Mutex,
+    thread,
+    time::Duration,
+};
+
+use x11_dl::{
+    xlib::{Display, Xlib},
+    xtest::Xf86vmode as XTest,
+};
+
+use super::{Input, InputBackend};
+use crate::{
+    errors::{Result, ResultExt},
+    key::{self, Key},
+    keyboard::KeyInput,
+    layout,
+    mouse::{MouseButton, MouseInput, PressType},
+};
+
+// one wheel notch, same as windows WHEEL_DELTA
+const WHEEL_DELTA: i32 = 120;
+
+// X keycode of an evdev key code
+const EVDEV_KEYCODE_OFFSET: c_uint = 8;
+
+// the compose key keysym
+const XK_MULTI_KEY: c_ulong = 0xFF20;
+
+// free keycodes used at most to type unicode chars
+const SCRATCH_POOL: usize = 16;
+
+// time for clients to handle the presses of a scratch keycode before it is
+// remapped, they look the keysym up only then
+const REMAP_WAIT: Duration = Duration::from_millis(50);
+
+fn scancode_keycode(scancode: u16) -> Result<c_uint> {
+    match key::scancode_to_evdev(scancode) {
+        Some(code) => Ok(code as c_uint + EVDEV_KEYCODE_OFFSET),
+        None => bail!("no X keycode for scan code {:#x}", scancode),
+    }
+}
+
+/// Free keycodes remapped in turn to type unicode chars, so a char typed
+/// right after another does not change the keycode of the first one
+#[derive(Debug, Default)]
+struct ScratchPool {
+    // keycodes and the char each is mapped to
+    slots: Vec<(c_uint, Option<char>)>,
+    next: usize,
+}
+
+impl ScratchPool {
+    fn new(keycodes: &[c_uint]) -> ScratchPool {
+        ScratchPool {
+            slots: keycodes.iter().map(|&keycode| (keycode, None)).collect(),
+            next: 0,
+        }
+    }
+
+    // the keycode to type `c`, and the char it must be remapped from, None
+    // when it types `c` already, Some(None) when it was free
+    fn slot(&mut self, c: char) -> (c_uint, Option<Option<char>>) {
+        if let Some(&(keycode, _)) = self.slots.iter().find(|(_, mapped)| *mapped == Some(c)) {
+            return (keycode, None);
+        }
+        let index = self.next;
+        self.next = (index + 1) % self.slots.len();
+        let (keycode, mapped) = &mut self.slots[index];
+        (*keycode, Some(mapped.replace(c)))
+    }
+
+    fn mapped(&self) -> impl Iterator<Item = c_uint> + '_ {
+        self.slots
+            .iter()
+            .filter(|(_, mapped)| mapped.is_some())
+            .map(|(keycode, _)| *keycode)
+    }
+}
+
+struct Connection {
+    xlib: Xlib,
+    xtest: XTest,
+    display: *mut Display,
+    // keycodes remapped for unicode chars, found at the first one
+    scratch: Option<ScratchPool>,
+}
+
+// the display is only touched with the mutex held
+unsafe impl Send for Connection {}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        if let Some(pool) = self.scratch.take() {
+            for keycode in pool.mapped() {
+                self.remap(keycode, 0);
+            }
+        }
+        unsafe { (self.xlib.XCloseDisplay)(self.display) };
+    }
+}
+
+/// Send inputs with the XTest extension of an X11 display
+pub struct X11Backend {
+    conn: Mutex<Connection>,
+}
+
+impl X11Backend {
+    /// Connect to the display named by `DISPLAY`
+    pub fn open() -> Result<X11Backend> {
+        let xlib = Xlib::open().chain_err(|| "load libX11 failed")?;
+        let xtest = XTest::open().chain_err(|| "load libXtst failed")?;
+
+        let display = unsafe { (xlib.XOpenDisplay)(std::ptr::null()) };
+        if display.is_null() {
+            bail!("XOpenDisplay() failed");
+        }
+        let conn = Connection {
+            xlib,
+            xtest,
+            display,
+            scratch: None,
+        };
+
+        let (mut event_base, mut error_base, mut major, mut minor) = (0, 0, 0, 0);
+        let ret = unsafe {
+            (conn.xtest.XTestQueryExtension)(
+                conn.display,
+                &mut event_base,
+                &mut error_base,
+                &mut major,
+                &mut minor,
+            )
+        };
+        if ret == 0 {
+            bail!("XTest extension is not available");
+        }
+
+        Ok(X11Backend {
+            conn: Mutex::new(conn),
+        })
+    }
+}
+
+impl Connection {
+    fn screen_size(&self) -> Result<(i32, i32)> {
+        let (mut root, mut x, mut y) = (0, 0, 0);
+        let (mut w, mut h, mut border, mut depth) = (0, 0, 0, 0);
+        let ret = unsafe {
+            (self.xlib.XGetGeometry)(
+                self.display,
+                (self.xlib.XDefaultRootWindow)(self.display),
+                &mut root,
+                &mut x,
+                &mut y,
+                &mut w,
+                &mut h,
+                &mut border,
+                &mut depth,
+            )
+        };
+        if ret == 0 {
+            bail!("XGetGeometry() failed");
+        }
+        Ok((w as i32, h as i32))
+    }
+
+    fn pointer(&self) -> (i32, i32) {
+        let (mut root, mut child) = (0, 0);
+        let (mut root_x, mut root_y, mut x, mut y, mut mask) = (0, 0, 0, 0, 0);
+        unsafe {
+            (self.xlib.XQueryPointer)(
+                self.display,
+                (self.xlib.XDefaultRootWindow)(self.display),
+                &mut root,
+                &mut child,
+                &mut root_x,
+                &mut root_y,
+                &mut x,
+                &mut y,
+                &mut mask,
+            )
+        };
+        (root_x, root_y)
+    }
+
+    // keys are physical positions, so use the evdev code of the key, the
+    // keycode of X servers on linux, keysyms are for keys without one
+    fn keycode(&self, key: Key) -> Result<c_uint> {
+        if let Some(code) = key.to_evdev() {
+            return Ok(code as c_uint + EVDEV_KEYCODE_OFFSET);
+        }
+        let keysym = match key.to_keysym() {
+            Some(keysym) => keysym,
+            None => bail!("no X keysym for key {:?}", key),
+        };
+        let keycode = unsafe { (self.xlib.XKeysymToKeycode)(self.display, keysym as c_ulong) };
+        if keycode == 0 {
+            bail!("keysym {:#x} is not in the keyboard mapping", keysym);
+        }
+        Ok(keycode as c_uint)
+    }
+
+    // keycodes without keysyms, remapped to type unicode chars
+    fn scratch_keycodes(&self) -> Result<Vec<c_uint>> {
+        let (mut min, mut max) = (0, 0);
+        unsafe { (self.xlib.XDisplayKeycodes)(self.display, &mut min, &mut max) };
+        let count = max - min + 1;
+        let mut per_keycode = 0;
+        let syms = unsafe {
+            (self.xlib.XGetKeyboardMapping)(self.display, min as u8, count, &mut per_keycode)
+        };
+        if syms.is_null() {
+            bail!("XGetKeyboardMapping() failed");
+        }
+        let per_keycode = per_keycode as usize;
+        let mapping = unsafe { std::slice::from_raw_parts(syms, count as usize * per_keycode) };
+        let scratch: Vec<c_uint> = mapping
+            .chunks(per_keycode)
+            .enumerate()
+            .rev()
+            .filter(|(_, syms)| syms.iter().all(|&sym| sym == 0))
+            .map(|(i, _)| (min as usize + i) as c_uint)
+            .take(SCRATCH_POOL)
+            .collect();
+        unsafe { (self.xlib.XFree)(syms as *mut _) };
+        if scratch.is_empty() {
+            bail!("no free keycode to type unicode chars");
+        }
+        Ok(scratch)
+    }
+
+    fn remap(&self, keycode: c_uint, keysym: c_ulong) {
+        let mut syms = [keysym, keysym];
+        unsafe {
+            (self.xlib.XChangeKeyboardMapping)(self.display, keycode as _, 2, syms.as_mut_ptr(), 1);
+            (self.xlib.XSync)(self.display, 0);
+        }
+    }
+
+    // the keycode typing `keysym` and whether with shift, None when it is
+    // not on the first two levels of a key
+    fn keysym_keycode(&self, keysym: c_ulong) -> Option<(c_uint, bool)> {
+        let keycode = unsafe { (self.xlib.XKeysymToKeycode)(self.display, keysym) };
+        if keycode == 0 {
+            return None;
+        }
+        let level = |index| unsafe { (self.xlib.XKeycodeToKeysym)(self.display, keycode, index) };
+        if level(0) == keysym {
+            Some((keycode as c_uint, false))
+        } else if level(1) == keysym {
+            Some((keycode as c_uint, true))
+        } else {
+            None
+        }
+    }
+
+    // the keys of the compose sequence typing `c`, like Multi_key ^ e for ê,
+    // None when the keyboard mapping has no compose key
+    fn compose_keycodes(&self, c: char) -> Option<[(c_uint, bool); 3]> {
+        let (dead, base) = layout::decompose(c).filter(|(_, base)| *base != ' ')?;
+        // both are latin-1, their keysym is their code point
+        Some([
+            self.keysym_keycode(XK_MULTI_KEY)?,
+            self.keysym_keycode(dead.compose_char() as c_ulong)?,
+            self.keysym_keycode(base as c_ulong)?,
+        ])
+    }
+
+    fn compose(&self, keycodes: &[(c_uint, bool)]) -> Result<()> {
+        let shift = self.keycode(Key::Shift)?;
+        let fake = self.xtest.XTestFakeKeyEvent;
+        for &(keycode, shifted) in keycodes {
+            unsafe {
+                if shifted {
+                    fake(self.display, shift, 1, 0);
+                }
+                fake(self.display, keycode, 1, 0);
+                fake(self.display, keycode, 0, 0);
+                if shifted {
+                    fake(self.display, shift, 0, 0);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // type an accented char with the compose key when there is one, or map
+    // the char to a free keycode and type it. Clients look the mapping up
+    // when handling the event, so keycodes are remapped in turn, waiting for
+    // the earlier presses of a keycode to be handled before remapping it.
+    fn unicode(&mut self, c: char) -> Result<()> {
+        if let Some(keycodes) = self.compose_keycodes(c) {
+            return self.compose(&keycodes);
+        }
+        if self.scratch.is_none() {
+            self.scratch = Some(ScratchPool::new(&self.scratch_keycodes()?));
+        }
+        let (keycode, remap) = self.scratch.as_mut().unwrap().slot(c);
+        if let Some(previous) = remap {
+            if previous.is_some() {
+                unsafe { (self.xlib.XSync)(self.display, 0) };
+                thread::sleep(REMAP_WAIT);
+            }
+            let keysym = match c as u32 {
+                code @ (0x20..=0x7E | 0xA0..=0xFF) => code,
+                code => 0x0100_0000 | code,
+            };
+            self.remap(keycode, keysym as c_ulong);
+        }
+        let fake = self.xtest.XTestFakeKeyEvent;
+        unsafe {
+            fake(self.display, keycode, 1, 0);
+            fake(self.display, keycode, 0, 0);
+        }
+        Ok(())
+    }
+
+    fn key(&mut self, key_input: &KeyInput) -> Result<()> {
+        let (keycode, down) = match key_input {
+            KeyInput::KeyDown(key) => (self.keycode(*key)?, 1),
+            KeyInput::KeyUp(key) => (self.keycode(*key)?, 0),
+            KeyInput::ScanCodeDown(code) => (scancode_keycode(*code)?, 1),
+            KeyInput::ScanCodeUp(code) => (scancode_keycode(*code)?, 0),
+            KeyInput::Unicode(c) => return self.unicode(*c),
+            // waited by `backend::input`
+            KeyInput::Delay(_) => return Ok(()),
+        };
+        unsafe { (self.xtest.XTestFakeKeyEvent)(self.display, keycode, down, 0) };
+        Ok(())
+    }
+
+    fn button(&self, button: c_uint, press: &PressType) {
+        let fake = self.xtest.XTestFakeButtonEvent;
+        unsafe {
+            match press {
+                PressType::PressDown => fake(self.display, button, 1, 0),
+                PressType::PressUp => fake(self.display, button, 0, 0),
+                PressType::Click => {
+                    fake(self.display, button, 1, 0);
+                    fake(self.display, button, 0, 0)
+                }
+            }
+        };
+    }
+
+    fn wheel(&self, amount: i32, positive: c_uint, negative: c_uint) {
+        let button = if amount > 0 { positive } else { negative };
+        let notches = (amount.abs() + WHEEL_DELTA / 2) / WHEEL_DELTA;
+        for _ in 0..notches.max(1) {
+            self.button(button, &PressType::Click);
+        }
+    }
+
+    fn mouse(&self, mouse_input: &MouseInput) -> Result<()> {
+        match mouse_input {
+            MouseInput::MoveTo(x, y) => {
+                let (w, h) = self.screen_size()?;
+                let x = (*x).clamp(0, w - 1);
+                let y = (*y).clamp(0, h - 1);
+                unsafe { (self.xtest.XTestFakeMotionEvent)(self.display, -1, x, y, 0) };
+            }
+            MouseInput::MoveDelta(x, y) => {
+                unsafe { (self.xtest.XTestFakeRelativeMotionEvent)(self.display, -1, *x, *y, 0) };
+            }
+            MouseInput::Press(button, press) => {
+                let button = match button {
+                    MouseButton::ButtonLeft => 1,
+                    MouseButton::ButtonMiddle => 2,
+                    MouseButton::ButtonRight => 3,
+                    MouseButton::ButtonX1 => 8,
+                    MouseButton::ButtonX2 => 9,
+                };
+                self.button(button, press);
+            }
+            MouseInput::Wheel(amount) if *amount != 0 => self.wheel(*amount, 4, 5),
+            MouseInput::HWheel(amount) if *amount != 0 => self.wheel(*amount, 7, 6),
+            MouseInput::Wheel(_) | MouseInput::HWheel(_) => {}
+        }
+        Ok(())
+    }
+}
+
+impl InputBackend for X11Backend {
+    fn input(&self, inputs: &[Input]) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let mut result = Ok(());
+        for input in inputs {
+            result = match input {
+                Input::Key(key_input) => conn.key(key_input),
+                Input::Mouse(mouse_input) => conn.mouse(mouse_input),
+            };
+            if result.is_err() {
+                break;
+            }
+        }
+        unsafe { (conn.xlib.XFlush)(conn.display) };
+        result
+    }
+
+    fn cursor_pos(&self) -> Result<(i32, i32)> {
+        Ok(self.conn.lock().unwrap().pointer())
+    }
+
+    // libX11 may be unloaded before the fini handlers run
+    fn sends_at_unload(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend;
+
+    fn is_key_down(conn: &Connection, key: Key) -> bool {
+        let keycode = conn.keycode(key).unwrap() as usize;
+        let mut keys = [0 as std::os::raw::c_char; 32];
+        unsafe { (conn.xlib.XQueryKeymap)(conn.display, keys.as_mut_ptr()) };
+        keys[keycode / 8] as u8 & (1 << (keycode % 8)) != 0
+    }
+
+    #[test]
+    fn scratch_pool() {
+        let mut pool = ScratchPool::new(&[200, 201]);
+        assert_eq!((200, Some(None)), pool.slot('中'));
+        // a different keycode for the next char, the first one is untouched
+        assert_eq!((201, Some(None)), pool.slot('文'));
+        assert_eq!((200, None), pool.slot('中'));
+        // the pool wraps, remapping the keycode of the oldest char
+        assert_eq!((200, Some(Some('中'))), pool.slot('字'));
+        assert_eq!((201, None), pool.slot('文'));
+        assert_eq!(vec![200, 201], pool.mapped().collect::<Vec<_>>());
+    }
+
+    // needs a X server, e.g. `xvfb-run cargo test -- --ignored x11`
+    #[test]
+    #[ignore]
+    fn x11_end_to_end() -> Result<()> {
+        let x11 = std::sync::Arc::new(X11Backend::open()?);
+        backend::with_backend(x11.clone(), || -> Result<()> {
+            crate::mouse::move_to(10, 20)?;
+            crate::keyboard::input_keys(&[KeyInput::KeyDown(Key::Shift)])
+        })?;
+        {
+            let conn = x11.conn.lock().unwrap();
+            unsafe { (conn.xlib.XSync)(conn.display, 0) };
+            assert_eq!((10, 20), conn.pointer());
+            assert!(is_key_down(&conn, Key::Shift));
+        }
+
+        backend::with_backend(x11.clone(), || {
+            crate::keyboard::input_keys(&[KeyInput::KeyUp(Key::Shift)])
+        })?;
+        {
+            let conn = x11.conn.lock().unwrap();
+            unsafe { (conn.xlib.XSync)(conn.display, 0) };
+            assert!(!is_key_down(&conn, Key::Shift));
+        }
+
+        // two chars in a row keep a keycode each
+        backend::with_backend(x11.clone(), || {
+            crate::keyboard::input_keys(&[KeyInput::Unicode('中'), KeyInput::Unicode('文')])
+        })?;
+        let conn = x11.conn.lock().unwrap();
+        let keysyms: Vec<c_ulong> = conn
+            .scratch
+            .as_ref()
+            .unwrap()
+            .mapped()
+            .map(|keycode| unsafe { (conn.xlib.XKeycodeToKeysym)(conn.display, keycode as u8, 0) })
+            .collect();
+        assert_eq!(vec![0x0100_4E2D, 0x0100_6587], keysyms);
+        Ok(())
+    }
+}
@@ -1,8 +1,14 @@
-use std::os::raw::{c_char, c_int};
+use std::{
+    os::raw::{c_char, c_int},
+    time::Duration,
+};
 
+#[cfg(windows)]
 use windows::Win32::Foundation::HWND;
 
-use crate::{keyboard, mouse, window};
+#[cfg(windows)]
+use crate::window;
+use crate::{backend, keyboard, layout::Layout, mouse, recorder};
 
 /// Keyboard input
 ///
@@ -13,9 +19,26 @@ use crate::{keyboard, mouse, window};
 /// - single key stroke: "abc" represents type key a, key b, key c.
 /// - uppercase key stroke: "ABC" represents press down shift key, type key a, key b, key c and release shift key.
 /// - key combination: "<ctrl+c>" represents press down ctrl key, type key c, release ctrl key.
-/// - press down: "<ctrl+>" represents press down ctrl key. warning: "<shift+>" will not work.
-/// - release: "<ctrl->" represents release ctrl key.
+/// - chars needing modifiers: "@" on a german layout represents press down AltGr (right alt), type key q, release AltGr.
+/// - chars behind dead keys: "ê" on a german layout represents type the ^ dead key, type key e.
+/// - press down: "<ctrl+>" represents press down ctrl key, held until released, also while typing chars.
+/// - release: "<ctrl->" represents release ctrl key. Keys left down are kept by default, see `kb_set_balance`.
+/// - repeat count: "<tab*5>" represents type tab key 5 times, "<ctrl+right*3>" represents press down ctrl key,
+///   type right key 3 times, release ctrl key. modifiers of a combination are held once, only the last key repeats.
+/// - hold: "<space~500ms>" represents press down space key, wait 500 milliseconds, release space key,
+///   "<shift+a~2s>" holds key a for 2 seconds while shift key is down. same units as delays.
 /// - virtual key code: "<13>" represents type enter key.
+/// - delay: "<sleep:250>" or "<wait 1s>" represents wait before the following keys, in milliseconds without unit,
+///   or with a `ms` or `s` unit. keys between delays are sent as one batch.
+/// - unicode: chars not on the keyboard layout, like "中文", are typed directly, "<u:4e2d>" types the code point U+4E2D.
+///   the X11 backend types accented chars with the compose key when the keyboard mapping has one.
+/// - alias: "<ok>" types the keys registered by `hk_alias`, like "<enter>".
+/// - mouse: "<click>", "<rclick>", "<mclick>", "<x1>" and "<x2>" click a button, "<click:300,400>" clicks at a position,
+///   "<click:+10,-5>" clicks after moving by an offset, "<click*2>" clicks twice, "<click+>" and "<click->" press and release.
+///   "<move:300,400>" or "<move:+10,-5>" moves, "<wheel:-120>" and "<hwheel:120>" scroll.
+///   keys and mouse actions of a string are sent in order as one batch, like "<click:300,400>hello<enter>".
+/// - scan code: "<sc:0x1e>" types the key at scan code 0x1E, extended keys are written like "<sc:0xe01c>" for numpad enter.
+///   holds, repeats, "<sc:0x1e+>" and "<sc:0x1e->" work like key names. see `kb_set_scancode_mode` to send all keys by scan code.
 /// - escaped chars:
 ///     - `<`: use `<lt>` instead
 ///     - `>`: use `<gt>` instead
@@ -163,11 +186,278 @@ pub extern "C" fn kb_input(keys: *const c_char) -> c_int {
     }
 }
 
+/// Keyboard input typed at a human pace, see `kb_input` for the keys
+///
+/// * `keys` - keys to send, see `kb_input` documentation
+/// * `delay_ms` - milliseconds to wait after a key is released
+/// * `hold_ms` - milliseconds a key is held down
+/// * `jitter_ms` - at most this many milliseconds randomly added to or taken from every wait
+///
+/// return 0 on success, -1 on failure
+#[no_mangle]
+pub extern "C" fn kb_input_with(
+    keys: *const c_char,
+    delay_ms: c_int,
+    hold_ms: c_int,
+    jitter_ms: c_int,
+) -> c_int {
+    if delay_ms < 0 || hold_ms < 0 || jitter_ms < 0 {
+        return -1;
+    }
+    let s = unsafe { std::ffi::CStr::from_ptr(keys).to_str().unwrap() };
+    let ms = |n: c_int| Duration::from_millis(n as u64);
+    let options = keyboard::TypingOptions {
+        delay: ms(delay_ms),
+        hold: ms(hold_ms),
+        jitter: ms(jitter_ms),
+        seed: None,
+    };
+    match keyboard::input_with(s, &options) {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("{}", e);
+            -1
+        }
+    }
+}
+
+/// Keyboard input typed like a typist at a number of words per minute, with random jitter
+///
+/// * `keys` - keys to send, see `kb_input` documentation
+/// * `wpm` - words of 5 chars per minute, above 0
+///
+/// return 0 on success, -1 on failure
+#[no_mangle]
+pub extern "C" fn kb_input_wpm(keys: *const c_char, wpm: c_int) -> c_int {
+    if wpm <= 0 {
+        return -1;
+    }
+    let s = unsafe { std::ffi::CStr::from_ptr(keys).to_str().unwrap() };
+    match keyboard::input_with(s, &keyboard::TypingOptions::from_wpm(wpm as u32)) {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("{}", e);
+            -1
+        }
+    }
+}
+
+/// Keyboard input with each key sent at its offset from the start, given by the delays
+///
+/// * `keys` - keys to send, see `kb_input` documentation
+/// * `max_late_us` - set to the microseconds the latest key was sent after its offset, may be null
+///
+/// return 0 on success, -1 on failure
+#[no_mangle]
+pub extern "C" fn kb_input_timed(keys: *const c_char, max_late_us: *mut c_int) -> c_int {
+    let s = unsafe { std::ffi::CStr::from_ptr(keys).to_str().unwrap() };
+    match keyboard::input_timed(s) {
+        Ok(played) => {
+            if !max_late_us.is_null() {
+                let late = played.iter().map(|p| p.late()).max().unwrap_or_default();
+                let late = late.as_micros().min(c_int::MAX as u128) as c_int;
+                unsafe { *max_late_us = late };
+            }
+            0
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            -1
+        }
+    }
+}
+
+/// Keyboard input in AutoHotkey `Send` syntax
+///
+/// * `keys` - like "^c", "+{Tab 3}", "{Enter}", "!{F4}", "#r" or "{Ctrl down}", `^` is ctrl, `+` shift,
+///   `!` alt and `#` win for the next key, `<` or `>` before them for the left or right key.
+///   Braces hold a key name with a repeat count, `down` or `up`, a char like "{!}", "{U+20AC}" or "{vk41}".
+///   "{Raw}" or "{Text}" sends the rest as plain text.
+///
+/// return 0 on success, -1 on failure
+#[no_mangle]
+pub extern "C" fn kb_input_ahk(keys: *const c_char) -> c_int {
+    let s = unsafe { std::ffi::CStr::from_ptr(keys).to_str().unwrap() };
+    match keyboard::input_ahk(s) {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("{}", e);
+            -1
+        }
+    }
+}
+
+/// Keyboard input in xdotool `key` syntax
+///
+/// * `keys` - chords separated by spaces, like "ctrl+alt+t super+Return shift+Tab", each a `+` separated list
+///   of X keysym names (`Return`, `BackSpace`, `Prior`, `Next`, `a`, `exclam`, `U20AC`, ...) or key names
+///   of `kb_input`, the last one pressed while the others are held
+///
+/// return 0 on success, -1 on failure
+#[no_mangle]
+pub extern "C" fn kb_input_xdo(keys: *const c_char) -> c_int {
+    let s = unsafe { std::ffi::CStr::from_ptr(keys).to_str().unwrap() };
+    match keyboard::input_xdo(s) {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("{}", e);
+            -1
+        }
+    }
+}
+
+/// Keyboard input in vim key notation
+///
+/// * `keys` - like "<C-w>v", "<M-x>", "<S-Tab>", ":wq<CR>", "<Esc>" or "<Leader>ff", brackets which
+///   aren't vim notation are typed as text, see `kb_set_vim_leaders` for the leader keys
+///
+/// return 0 on success, -1 on failure
+#[no_mangle]
+pub extern "C" fn kb_input_vim(keys: *const c_char) -> c_int {
+    let s = unsafe { std::ffi::CStr::from_ptr(keys).to_str().unwrap() };
+    match keyboard::input_vim(s) {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("{}", e);
+            -1
+        }
+    }
+}
+
+/// Set the keys typed for `<Leader>` and `<LocalLeader>` by `kb_input_vim`
+///
+/// * `leader` - keys in vim notation, like "<Space>" or ",", null for a backslash
+/// * `local_leader` - keys in vim notation, null for a backslash
+///
+/// return 0 on success, -1 on failure
+#[no_mangle]
+pub extern "C" fn kb_set_vim_leaders(leader: *const c_char, local_leader: *const c_char) -> c_int {
+    let mut leaders = keyboard::VimLeaders::default();
+    for (keys, ptr) in [
+        (&mut leaders.leader, leader),
+        (&mut leaders.local_leader, local_leader),
+    ] {
+        if ptr.is_null() {
+            continue;
+        }
+        match unsafe { std::ffi::CStr::from_ptr(ptr) }.to_str() {
+            Ok(s) => *keys = s.to_string(),
+            Err(e) => {
+                eprintln!("{}", e);
+                return -1;
+            }
+        }
+    }
+    keyboard::set_vim_leaders(leaders);
+    0
+}
+
+/// Check a key string without sending any input
+///
+/// * `keys` - keys to check, see `kb_input` documentation
+/// * `message` - buffer filled with the nul terminated error message, the key string
+///   and carets under the error, truncated to `size`, may be null
+/// * `size` - size of `message` in bytes
+/// * `column` - filled with the 1-based char column of the error, 0 when valid, may be null
+///
+/// return 0 when valid, -1 on failure
+#[no_mangle]
+pub extern "C" fn kb_check(
+    keys: *const c_char,
+    message: *mut c_char,
+    size: usize,
+    column: *mut c_int,
+) -> c_int {
+    let s = unsafe { std::ffi::CStr::from_ptr(keys).to_str().unwrap() };
+    let (ret, text, col) = match keyboard::parse_input(s) {
+        Ok(_) => (0, String::new(), 0),
+        Err(e) => (-1, e.render(), e.column()),
+    };
+    if !column.is_null() {
+        unsafe { *column = col as c_int };
+    }
+    if !message.is_null() && size > 0 {
+        let len = truncated_len(&text, size - 1);
+        unsafe {
+            std::ptr::copy_nonoverlapping(text.as_ptr(), message as *mut u8, len);
+            *message.add(len) = 0;
+        }
+    }
+    ret
+}
+
+// the length of `text` cut to `max` bytes, not splitting a char
+fn truncated_len(text: &str, max: usize) -> usize {
+    let mut len = text.len().min(max);
+    while !text.is_char_boundary(len) {
+        len -= 1;
+    }
+    len
+}
+
+/// Set the keyboard layout used to type chars
+///
+/// * `layout` - a builtin layout (`us`, `uk`, `de`, `fr`), a path to a layout file
+///   or a XKB symbols file, or null for the system layout
+///
+/// return 0 on success, -1 on failure
+#[no_mangle]
+pub extern "C" fn kb_set_layout(layout: *const c_char) -> c_int {
+    if layout.is_null() {
+        keyboard::set_layout(None);
+        return 0;
+    }
+    let s = unsafe { std::ffi::CStr::from_ptr(layout).to_str().unwrap() };
+    match Layout::builtin(s).or_else(|_| Layout::load(s)) {
+        Ok(layout) => {
+            keyboard::set_layout(Some(layout));
+            0
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            -1
+        }
+    }
+}
+
+/// Set what `kb_input` does with keys still held at the end of a key string, e.g. ctrl after "<ctrl+>abc"
+///
+/// * `balance` - `0` to leave them down, `1` to fail, `2` to release them
+///
+/// return 0 on success, -1 on failure
+#[no_mangle]
+pub extern "C" fn kb_set_balance(balance: c_int) -> c_int {
+    match keyboard::Balance::from_i32(balance) {
+        Some(balance) => {
+            keyboard::set_balance(balance);
+            0
+        }
+        None => -1,
+    }
+}
+
+/// Send keys by scan code instead of virtual key, for games reading DirectInput or raw input
+///
+/// * `enable` - `1` to send scan codes, `0` to send virtual keys
+///
+/// return 0 on success, -1 on failure
+#[no_mangle]
+pub extern "C" fn kb_set_scancode_mode(enable: c_int) -> c_int {
+    match enable {
+        0 | 1 => {
+            keyboard::set_scancode_mode(enable == 1);
+            0
+        }
+        _ => -1,
+    }
+}
+
 /// Wait for release these keys
 ///
 /// * `keys` - keys to wait for release, see `kb_input` documentation
 ///
 /// return 0 on success, -1 on failure
+#[cfg(windows)]
 #[no_mangle]
 pub extern "C" fn kb_wait_keys_up(keys: *const c_char) -> c_int {
     let s = unsafe { std::ffi::CStr::from_ptr(keys).to_str().unwrap() };
@@ -185,6 +475,7 @@ pub extern "C" fn kb_wait_keys_up(keys: *const c_char) -> c_int {
 /// * `keys` - keys to wait for release, see `kb_input` documentation, must be key combination form (e.g. "<ctrl+a>")
 ///
 /// return 0 on success, -1 on failure
+#[cfg(windows)]
 #[no_mangle]
 pub extern "C" fn hotkey_register(keys: *const c_char) -> c_int {
     let s = unsafe { std::ffi::CStr::from_ptr(keys).to_str().unwrap() };
@@ -200,6 +491,7 @@ pub extern "C" fn hotkey_register(keys: *const c_char) -> c_int {
 /// Unregister hotkey with hotkey id.
 ///
 /// * `id` - hotkey id, which was returned by `hotkey_register`
+#[cfg(windows)]
 #[no_mangle]
 pub extern "C" fn hotkey_unregister(id: c_int) {
     keyboard::hotkey_unregister(id)
@@ -208,6 +500,7 @@ pub extern "C" fn hotkey_unregister(id: c_int) {
 /// Wait for hotkey input, when any hotkey pressed, return hotkey id.
 ///
 /// return hotkey id on success, -1 on failure
+#[cfg(windows)]
 #[no_mangle]
 pub extern "C" fn hotkey_wait() -> c_int {
     match keyboard::hotkey_wait() {
@@ -236,6 +529,46 @@ pub extern "C" fn mouse_move_to(x: i32, y: i32) -> c_int {
     }
 }
 
+/// Mouse move to an absolute position along a path, with a move per point on the way. With the
+/// uinput backend the path starts where `mouse_move_to` moved the cursor last, and fails before.
+///
+/// * `x` - x position
+/// * `y` - y position
+/// * `duration_ms` - milliseconds from the cursor position to `x`, `y`
+/// * `shape` - `0` for a line, `1` for a Bezier curve, `2` for WindMouse
+/// * `easing` - `0` linear, `1` ease in, `2` ease out, `3` ease in and out
+///
+/// return 0 on success, -1 on failure
+#[no_mangle]
+pub extern "C" fn mouse_move_to_with(
+    x: i32,
+    y: i32,
+    duration_ms: c_int,
+    shape: c_int,
+    easing: c_int,
+) -> c_int {
+    let (shape, easing) = match (
+        mouse::PathShape::from_i32(shape),
+        mouse::Easing::from_i32(easing),
+    ) {
+        (Some(shape), Some(easing)) if duration_ms >= 0 => (shape, easing),
+        _ => return -1,
+    };
+    let path = mouse::MovePath {
+        shape,
+        easing,
+        duration: Duration::from_millis(duration_ms as u64),
+        ..mouse::MovePath::default()
+    };
+    match mouse::move_to_with(x, y, &path) {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("{}", e);
+            -1
+        }
+    }
+}
+
 /// Mouse move to relative position.
 ///
 /// * `x` - x delta
@@ -377,9 +710,108 @@ pub extern "C" fn mouse_button_press(button: c_int, press: c_int) -> c_int {
     }
 }
 
+/// Start recording the keyboard and mouse, until `macro_record_stop`
+///
+/// return 0 on success, -1 on failure
+#[no_mangle]
+pub extern "C" fn macro_record_start() -> c_int {
+    match recorder::start() {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("{}", e);
+            -1
+        }
+    }
+}
+
+/// Stop recording, return the id of the recorded macro
+///
+/// return macro id on success, -1 on failure
+#[no_mangle]
+pub extern "C" fn macro_record_stop() -> c_int {
+    match recorder::stop() {
+        Ok(m) => recorder::keep(m),
+        Err(e) => {
+            eprintln!("{}", e);
+            -1
+        }
+    }
+}
+
+/// Load a macro file, JSON or text, return the id of the macro
+///
+/// * `path` - path of a file saved by `macro_save` or written by hand
+///
+/// return macro id on success, -1 on failure
+#[no_mangle]
+pub extern "C" fn macro_load(path: *const c_char) -> c_int {
+    let path = unsafe { std::ffi::CStr::from_ptr(path).to_str().unwrap() };
+    match recorder::Macro::load(path) {
+        Ok(m) => recorder::keep(m),
+        Err(e) => {
+            eprintln!("{}", e);
+            -1
+        }
+    }
+}
+
+/// Save a macro to a file
+///
+/// * `id` - macro id, which was returned by `macro_record_stop` or `macro_load`
+/// * `path` - path of the file, JSON when it ends with `.json`, a step per line otherwise
+///
+/// return 0 on success, -1 on failure
+#[no_mangle]
+pub extern "C" fn macro_save(id: c_int, path: *const c_char) -> c_int {
+    let path = unsafe { std::ffi::CStr::from_ptr(path).to_str().unwrap() };
+    match recorder::kept(id).and_then(|m| m.save(path)) {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("{}", e);
+            -1
+        }
+    }
+}
+
+/// Play a macro
+///
+/// * `id` - macro id, which was returned by `macro_record_stop` or `macro_load`
+/// * `speed` - `1.0` as recorded, `2.0` twice as fast, 0.001 or above
+/// * `repeat` - times to play the macro, from 0 to 10000
+///
+/// return 0 on success, -1 on failure
+#[no_mangle]
+pub extern "C" fn macro_play(id: c_int, speed: f64, repeat: c_int) -> c_int {
+    if repeat < 0 {
+        return -1;
+    }
+    match recorder::kept(id).and_then(|m| m.play(speed, repeat as u32)) {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("{}", e);
+            -1
+        }
+    }
+}
+
+/// Free a macro
+///
+/// * `id` - macro id, which was returned by `macro_record_stop` or `macro_load`
+///
+/// return 0 on success, -1 on failure
+#[no_mangle]
+pub extern "C" fn macro_free(id: c_int) -> c_int {
+    if recorder::forget(id) {
+        0
+    } else {
+        -1
+    }
+}
+
 /// Find window by caption and class name, return handle
 ///
 /// return handle on success, 0 on failure
+#[cfg(windows)]
 #[no_mangle]
 pub extern "C" fn window_find(caption: *const c_char, class: *const c_char) -> isize {
     let cap_str = unsafe { std::ffi::CStr::from_ptr(caption).to_str().unwrap() };
@@ -402,6 +834,7 @@ pub extern "C" fn window_find(caption: *const c_char, class: *const c_char) -> i
 /// * `bottom` - pointer for bottom position
 ///
 /// return 0 on success, -1 on failure, when success, left, right, top, bottom will be set
+#[cfg(windows)]
 #[no_mangle]
 pub extern "C" fn window_get_rect(
     hwnd: isize,
@@ -423,3 +856,101 @@ pub extern "C" fn window_get_rect(
         None => -1,
     }
 }
+
+/// Select input backend used by all keyboard and mouse functions
+///
+/// * `kind` - `0` for win32 `SendInput`, `1` for recording in memory, `2` for printing to stdout,
+///   `3` for X11 XTest, `4` for linux uinput virtual devices on a 1920x1080 screen, see
+///   `hk_set_uinput_backend` for other sizes
+///
+/// return 0 on success, -1 on failure
+#[no_mangle]
+pub extern "C" fn hk_set_backend(kind: c_int) -> c_int {
+    let kind = match backend::BackendKind::from_i32(kind) {
+        Some(kind) => kind,
+        None => return -1,
+    };
+
+    match backend::select(kind) {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("{}", e);
+            -1
+        }
+    }
+}
+
+/// Select the linux uinput backend, with absolute mouse moves mapped to the screen size
+///
+/// * `width` - screen width in pixels
+/// * `height` - screen height in pixels
+///
+/// return 0 on success, -1 on failure
+#[no_mangle]
+pub extern "C" fn hk_set_uinput_backend(width: c_int, height: c_int) -> c_int {
+    match backend::select_uinput(width, height) {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("{}", e);
+            -1
+        }
+    }
+}
+
+/// Release all keys and mouse buttons sent down and not released yet, also done when the library
+/// is unloaded on linux. On windows nothing is released at unload, call this before exiting.
+///
+/// return 0 on success, -1 on failure
+#[no_mangle]
+pub extern "C" fn hk_release_held() -> c_int {
+    match backend::release_held() {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("{}", e);
+            -1
+        }
+    }
+}
+
+/// Register an alias usable in brackets of key strings
+///
+/// * `name` - alias name, letters, digits or '_', not a key name, like "ok" or "copy"
+/// * `keys` - key string the alias expands to, like "<enter>" or "<ctrl+c>", null removes the alias
+///
+/// return 0 on success, -1 on failure
+#[no_mangle]
+pub extern "C" fn hk_alias(name: *const c_char, keys: *const c_char) -> c_int {
+    let name = unsafe { std::ffi::CStr::from_ptr(name).to_str().unwrap() };
+    if keys.is_null() {
+        return if keyboard::remove_alias(name) { 0 } else { -1 };
+    }
+    let keys = unsafe { std::ffi::CStr::from_ptr(keys).to_str().unwrap() };
+    match keyboard::set_alias(name, keys) {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("{}", e);
+            -1
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_message() {
+        let keys = std::ffi::CString::new("é<entr>").unwrap();
+        let mut message = [0 as c_char; 2];
+        let mut column = 0;
+        assert_eq!(
+            -1,
+            kb_check(keys.as_ptr(), message.as_mut_ptr(), 0, &mut column)
+        );
+        assert_eq!(3, column);
+        // a char cut in half is left out
+        assert_eq!(0, truncated_len("é", 1));
+        assert_eq!(2, truncated_len("éa", 2));
+        assert_eq!(3, truncated_len("éa", 10));
+    }
+}
@@ -0,0 +1,626 @@
+use std::{collections::HashMap, fmt, str::FromStr};
+
+use crate::errors::{Error, Result};
+
+macro_rules! keys {
+    ($($key:ident = $vk:expr, $evdev:expr, $keysym:expr, $hid:expr, [$($name:expr),*];)*) => {
+        /// A physical key, independent of the platform.
+        ///
+        /// Keys are named after their position on a US keyboard, e.g. `Key::Y`
+        /// is the key producing `z` on a German layout.
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+        pub enum Key {
+            $($key,)*
+        }
+
+        // key, win32 virtual key, evdev KEY_*, X11 keysym, USB HID usage id, names.
+        // A 0 in the rows below is no such code on that platform, never a code
+        const KEY_TABLE: &[Entry] = &[
+            $((
+                Key::$key,
+                $vk,
+                code16($evdev),
+                code32($keysym),
+                code16($hid),
+                &[$($name),*],
+            ),)*
+        ];
+
+        /// Parse a key from its variant name, e.g. "PageDown"
+        impl FromStr for Key {
+            type Err = Error;
+
+            fn from_str(s: &str) -> Result<Key> {
+                match s {
+                    $(stringify!($key) => Ok(Key::$key),)*
+                    _ => bail!("unknown key {}", s),
+                }
+            }
+        }
+    };
+}
+
+// one row per win32 virtual key in virtual key order, `VkXX` for the codes
+// without a key of their own, like the mouse buttons and unassigned codes
+keys! {
+    Vk00 = 0x00, 0, 0, 0, [];
+    Vk01 = 0x01, 0, 0, 0, [];
+    Vk02 = 0x02, 0, 0, 0, [];
+    Vk03 = 0x03, 0, 0, 0, [];
+    Vk04 = 0x04, 0, 0, 0, [];
+    Vk05 = 0x05, 0, 0, 0, [];
+    Vk06 = 0x06, 0, 0, 0, [];
+    Vk07 = 0x07, 0, 0, 0, [];
+    Backspace = 0x08, 14, 0xFF08, 0x2A, ["backspace", "bs"];
+    Tab = 0x09, 15, 0xFF09, 0x2B, ["tab"];
+    Vk0A = 0x0A, 0, 0, 0, [];
+    Vk0B = 0x0B, 0, 0, 0, [];
+    Clear = 0x0C, 355, 0xFF0B, 0x9C, ["clear"];
+    Enter = 0x0D, 28, 0xFF0D, 0x28, ["enter", "return"];
+    Vk0E = 0x0E, 0, 0, 0, [];
+    Vk0F = 0x0F, 0, 0, 0, [];
+    Shift = 0x10, 0, 0, 0, ["shift"];
+    Ctrl = 0x11, 0, 0, 0, ["ctrl"];
+    Alt = 0x12, 0, 0, 0, ["alt"];
+    Pause = 0x13, 119, 0xFF13, 0x48, ["pause"];
+    CapsLock = 0x14, 58, 0xFFE5, 0x39, ["caps"];
+    Kana = 0x15, 93, 0xFF27, 0x88, ["kana", "hangul"];
+    ImeOn = 0x16, 0, 0, 0, ["ime_on"];
+    Junja = 0x17, 0, 0xFF38, 0, ["junja"];
+    Final = 0x18, 0, 0, 0, ["final"];
+    Hanja = 0x19, 123, 0xFF34, 0x91, ["hanja", "kanji"];
+    ImeOff = 0x1A, 0, 0, 0, ["ime_off"];
+    Esc = 0x1B, 1, 0xFF1B, 0x29, ["esc"];
+    Convert = 0x1C, 92, 0xFF23, 0x8A, ["convert"];
+    NonConvert = 0x1D, 94, 0xFF22, 0x8B, ["nonconvert"];
+    Accept = 0x1E, 0, 0, 0, ["accept"];
+    ModeChange = 0x1F, 0, 0xFF7E, 0, ["modechange"];
+    Space = 0x20, 57, 0x0020, 0x2C, ["space"];
+    PageUp = 0x21, 104, 0xFF55, 0x4B, ["pageup"];
+    PageDown = 0x22, 109, 0xFF56, 0x4E, ["pagedown"];
+    End = 0x23, 107, 0xFF57, 0x4D, ["end"];
+    Home = 0x24, 102, 0xFF50, 0x4A, ["home"];
+    Left = 0x25, 105, 0xFF51, 0x50, ["left"];
+    Up = 0x26, 103, 0xFF52, 0x52, ["up"];
+    Right = 0x27, 106, 0xFF53, 0x4F, ["right"];
+    Down = 0x28, 108, 0xFF54, 0x51, ["down"];
+    Select = 0x29, 353, 0xFF60, 0x77, ["select"];
+    Print = 0x2A, 210, 0, 0, ["print"];
+    Execute = 0x2B, 0, 0xFF62, 0x74, ["execute"];
+    PrintScreen = 0x2C, 99, 0xFF61, 0x46, ["printscreen", "ps"];
+    Insert = 0x2D, 110, 0xFF63, 0x49, ["insert", "ins"];
+    Delete = 0x2E, 111, 0xFFFF, 0x4C, ["delete", "del"];
+    Help = 0x2F, 138, 0xFF6A, 0x75, ["help"];
+    Digit0 = 0x30, 11, 0x0030, 0x27, [];
+    Digit1 = 0x31, 2, 0x0031, 0x1E, [];
+    Digit2 = 0x32, 3, 0x0032, 0x1F, [];
+    Digit3 = 0x33, 4, 0x0033, 0x20, [];
+    Digit4 = 0x34, 5, 0x0034, 0x21, [];
+    Digit5 = 0x35, 6, 0x0035, 0x22, [];
+    Digit6 = 0x36, 7, 0x0036, 0x23, [];
+    Digit7 = 0x37, 8, 0x0037, 0x24, [];
+    Digit8 = 0x38, 9, 0x0038, 0x25, [];
+    Digit9 = 0x39, 10, 0x0039, 0x26, [];
+    Vk3A = 0x3A, 0, 0, 0, [];
+    Vk3B = 0x3B, 0, 0, 0, [];
+    Vk3C = 0x3C, 0, 0, 0, [];
+    Vk3D = 0x3D, 0, 0, 0, [];
+    Vk3E = 0x3E, 0, 0, 0, [];
+    Vk3F = 0x3F, 0, 0, 0, [];
+    Vk40 = 0x40, 0, 0, 0, [];
+    A = 0x41, 30, 0x0061, 0x04, [];
+    B = 0x42, 48, 0x0062, 0x05, [];
+    C = 0x43, 46, 0x0063, 0x06, [];
+    D = 0x44, 32, 0x0064, 0x07, [];
+    E = 0x45, 18, 0x0065, 0x08, [];
+    F = 0x46, 33, 0x0066, 0x09, [];
+    G = 0x47, 34, 0x0067, 0x0A, [];
+    H = 0x48, 35, 0x0068, 0x0B, [];
+    I = 0x49, 23, 0x0069, 0x0C, [];
+    J = 0x4A, 36, 0x006A, 0x0D, [];
+    K = 0x4B, 37, 0x006B, 0x0E, [];
+    L = 0x4C, 38, 0x006C, 0x0F, [];
+    M = 0x4D, 50, 0x006D, 0x10, [];
+    N = 0x4E, 49, 0x006E, 0x11, [];
+    O = 0x4F, 24, 0x006F, 0x12, [];
+    P = 0x50, 25, 0x0070, 0x13, [];
+    Q = 0x51, 16, 0x0071, 0x14, [];
+    R = 0x52, 19, 0x0072, 0x15, [];
+    S = 0x53, 31, 0x0073, 0x16, [];
+    T = 0x54, 20, 0x0074, 0x17, [];
+    U = 0x55, 22, 0x0075, 0x18, [];
+    V = 0x56, 47, 0x0076, 0x19, [];
+    W = 0x57, 17, 0x0077, 0x1A, [];
+    X = 0x58, 45, 0x0078, 0x1B, [];
+    Y = 0x59, 21, 0x0079, 0x1C, [];
+    Z = 0x5A, 44, 0x007A, 0x1D, [];
+    LWin = 0x5B, 125, 0xFFEB, 0xE3, ["win", "lwin"];
+    RWin = 0x5C, 126, 0xFFEC, 0xE7, ["rwin"];
+    Apps = 0x5D, 127, 0xFF67, 0x65, ["apps"];
+    Vk5E = 0x5E, 0, 0, 0, [];
+    Sleep = 0x5F, 142, 0x1008FF2F, 0, ["sleep"];
+    Numpad0 = 0x60, 82, 0xFFB0, 0x62, ["numpad0"];
+    Numpad1 = 0x61, 79, 0xFFB1, 0x59, ["numpad1"];
+    Numpad2 = 0x62, 80, 0xFFB2, 0x5A, ["numpad2"];
+    Numpad3 = 0x63, 81, 0xFFB3, 0x5B, ["numpad3"];
+    Numpad4 = 0x64, 75, 0xFFB4, 0x5C, ["numpad4"];
+    Numpad5 = 0x65, 76, 0xFFB5, 0x5D, ["numpad5"];
+    Numpad6 = 0x66, 77, 0xFFB6, 0x5E, ["numpad6"];
+    Numpad7 = 0x67, 71, 0xFFB7, 0x5F, ["numpad7"];
+    Numpad8 = 0x68, 72, 0xFFB8, 0x60, ["numpad8"];
+    Numpad9 = 0x69, 73, 0xFFB9, 0x61, ["numpad9"];
+    Multiply = 0x6A, 55, 0xFFAA, 0x55, ["multiply"];
+    Add = 0x6B, 78, 0xFFAB, 0x57, ["add"];
+    Separator = 0x6C, 121, 0xFFAC, 0x85, ["separator"];
+    Subtract = 0x6D, 74, 0xFFAD, 0x56, ["subtract"];
+    Decimal = 0x6E, 83, 0xFFAE, 0x63, ["decimal"];
+    Divide = 0x6F, 98, 0xFFAF, 0x54, ["divide"];
+    F1 = 0x70, 59, 0xFFBE, 0x3A, ["f1"];
+    F2 = 0x71, 60, 0xFFBF, 0x3B, ["f2"];
+    F3 = 0x72, 61, 0xFFC0, 0x3C, ["f3"];
+    F4 = 0x73, 62, 0xFFC1, 0x3D, ["f4"];
+    F5 = 0x74, 63, 0xFFC2, 0x3E, ["f5"];
+    F6 = 0x75, 64, 0xFFC3, 0x3F, ["f6"];
+    F7 = 0x76, 65, 0xFFC4, 0x40, ["f7"];
+    F8 = 0x77, 66, 0xFFC5, 0x41, ["f8"];
+    F9 = 0x78, 67, 0xFFC6, 0x42, ["f9"];
+    F10 = 0x79, 68, 0xFFC7, 0x43, ["f10"];
+    F11 = 0x7A, 87, 0xFFC8, 0x44, ["f11"];
+    F12 = 0x7B, 88, 0xFFC9, 0x45, ["f12"];
+    F13 = 0x7C, 183, 0xFFCA, 0x68, ["f13"];
+    F14 = 0x7D, 184, 0xFFCB, 0x69, ["f14"];
+    F15 = 0x7E, 185, 0xFFCC, 0x6A, ["f15"];
+    F16 = 0x7F, 186, 0xFFCD, 0x6B, ["f16"];
+    F17 = 0x80, 187, 0xFFCE, 0x6C, ["f17"];
+    F18 = 0x81, 188, 0xFFCF, 0x6D, ["f18"];
+    F19 = 0x82, 189, 0xFFD0, 0x6E, ["f19"];
+    F20 = 0x83, 190, 0xFFD1, 0x6F, ["f20"];
+    F21 = 0x84, 191, 0xFFD2, 0x70, ["f21"];
+    F22 = 0x85, 192, 0xFFD3, 0x71, ["f22"];
+    F23 = 0x86, 193, 0xFFD4, 0x72, ["f23"];
+    F24 = 0x87, 194, 0xFFD5, 0x73, ["f24"];
+    Vk88 = 0x88, 0, 0, 0, [];
+    Vk89 = 0x89, 0, 0, 0, [];
+    Vk8A = 0x8A, 0, 0, 0, [];
+    Vk8B = 0x8B, 0, 0, 0, [];
+    Vk8C = 0x8C, 0, 0, 0, [];
+    Vk8D = 0x8D, 0, 0, 0, [];
+    Vk8E = 0x8E, 0, 0, 0, [];
+    Vk8F = 0x8F, 0, 0, 0, [];
+    NumLock = 0x90, 69, 0xFF7F, 0x53, ["numlock"];
+    ScrollLock = 0x91, 70, 0xFF14, 0x47, ["scroll"];
+    Vk92 = 0x92, 0, 0, 0, [];
+    Vk93 = 0x93, 0, 0, 0, [];
+    Vk94 = 0x94, 0, 0, 0, [];
+    Vk95 = 0x95, 0, 0, 0, [];
+    Vk96 = 0x96, 0, 0, 0, [];
+    Vk97 = 0x97, 0, 0, 0, [];
+    Vk98 = 0x98, 0, 0, 0, [];
+    Vk99 = 0x99, 0, 0, 0, [];
+    Vk9A = 0x9A, 0, 0, 0, [];
+    Vk9B = 0x9B, 0, 0, 0, [];
+    Vk9C = 0x9C, 0, 0, 0, [];
+    Vk9D = 0x9D, 0, 0, 0, [];
+    Vk9E = 0x9E, 0, 0, 0, [];
+    Vk9F = 0x9F, 0, 0, 0, [];
+    LShift = 0xA0, 42, 0xFFE1, 0xE1, ["lshift"];
+    RShift = 0xA1, 54, 0xFFE2, 0xE5, ["rshift"];
+    LCtrl = 0xA2, 29, 0xFFE3, 0xE0, ["lctrl"];
+    RCtrl = 0xA3, 97, 0xFFE4, 0xE4, ["rctrl"];
+    LAlt = 0xA4, 56, 0xFFE9, 0xE2, ["lalt"];
+    RAlt = 0xA5, 100, 0xFFEA, 0xE6, ["ralt"];
+    BrowserBack = 0xA6, 158, 0x1008FF26, 0, ["browser_back"];
+    BrowserForward = 0xA7, 159, 0x1008FF27, 0, ["browser_forward"];
+    BrowserRefresh = 0xA8, 173, 0x1008FF29, 0, ["browser_refresh"];
+    BrowserStop = 0xA9, 128, 0x1008FF28, 0x78, ["browser_stop"];
+    BrowserSearch = 0xAA, 217, 0x1008FF1B, 0, ["browser_search"];
+    BrowserFavorites = 0xAB, 156, 0x1008FF30, 0, ["browser_favorites"];
+    BrowserHome = 0xAC, 172, 0x1008FF18, 0, ["browser_home"];
+    VolumeMute = 0xAD, 113, 0x1008FF12, 0x7F, ["volume_mute"];
+    VolumeDown = 0xAE, 114, 0x1008FF11, 0x81, ["volume_down"];
+    VolumeUp = 0xAF, 115, 0x1008FF13, 0x80, ["volume_up"];
+    MediaNextTrack = 0xB0, 163, 0x1008FF17, 0, ["media_next_track"];
+    MediaPrevTrack = 0xB1, 165, 0x1008FF16, 0, ["media_prev_track"];
+    MediaStop = 0xB2, 166, 0x1008FF15, 0, ["media_stop"];
+    MediaPlayPause = 0xB3, 164, 0x1008FF14, 0, ["media_play_pause"];
+    LaunchMail = 0xB4, 155, 0x1008FF19, 0, ["launch_mail"];
+    LaunchMediaSelect = 0xB5, 226, 0x1008FF32, 0, ["launch_media_select"];
+    LaunchApp1 = 0xB6, 157, 0x1008FF33, 0, ["launch_app1"];
+    LaunchApp2 = 0xB7, 140, 0x1008FF1D, 0, ["launch_app2"];
+    VkB8 = 0xB8, 0, 0, 0, [];
+    VkB9 = 0xB9, 0, 0, 0, [];
+    Semicolon = 0xBA, 39, 0x003B, 0x33, [];
+    Equal = 0xBB, 13, 0x003D, 0x2E, [];
+    Comma = 0xBC, 51, 0x002C, 0x36, [];
+    Minus = 0xBD, 12, 0x002D, 0x2D, [];
+    Period = 0xBE, 52, 0x002E, 0x37, [];
+    Slash = 0xBF, 53, 0x002F, 0x38, [];
+    Backquote = 0xC0, 41, 0x0060, 0x35, [];
+    VkC1 = 0xC1, 0, 0, 0, [];
+    VkC2 = 0xC2, 0, 0, 0, [];
+    VkC3 = 0xC3, 0, 0, 0, [];
+    VkC4 = 0xC4, 0, 0, 0, [];
+    VkC5 = 0xC5, 0, 0, 0, [];
+    VkC6 = 0xC6, 0, 0, 0, [];
+    VkC7 = 0xC7, 0, 0, 0, [];
+    VkC8 = 0xC8, 0, 0, 0, [];
+    VkC9 = 0xC9, 0, 0, 0, [];
+    VkCA = 0xCA, 0, 0, 0, [];
+    VkCB = 0xCB, 0, 0, 0, [];
+    VkCC = 0xCC, 0, 0, 0, [];
+    VkCD = 0xCD, 0, 0, 0, [];
+    VkCE = 0xCE, 0, 0, 0, [];
+    VkCF = 0xCF, 0, 0, 0, [];
+    VkD0 = 0xD0, 0, 0, 0, [];
+    VkD1 = 0xD1, 0, 0, 0, [];
+    VkD2 = 0xD2, 0, 0, 0, [];
+    VkD3 = 0xD3, 0, 0, 0, [];
+    VkD4 = 0xD4, 0, 0, 0, [];
+    VkD5 = 0xD5, 0, 0, 0, [];
+    VkD6 = 0xD6, 0, 0, 0, [];
+    VkD7 = 0xD7, 0, 0, 0, [];
+    VkD8 = 0xD8, 0, 0, 0, [];
+    VkD9 = 0xD9, 0, 0, 0, [];
+    VkDA = 0xDA, 0, 0, 0, [];
+    LeftBracket = 0xDB, 26, 0x005B, 0x2F, [];
+    Backslash = 0xDC, 43, 0x005C, 0x31, [];
+    RightBracket = 0xDD, 27, 0x005D, 0x30, [];
+    Quote = 0xDE, 40, 0x0027, 0x34, [];
+    Oem8 = 0xDF, 0, 0, 0, [];
+    VkE0 = 0xE0, 0, 0, 0, [];
+    VkE1 = 0xE1, 0, 0, 0, [];
+    IntlBackslash = 0xE2, 86, 0x003C, 0x64, [];
+    VkE3 = 0xE3, 0, 0, 0, [];
+    VkE4 = 0xE4, 0, 0, 0, [];
+    VkE5 = 0xE5, 0, 0, 0, [];
+    VkE6 = 0xE6, 0, 0, 0, [];
+    VkE7 = 0xE7, 0, 0, 0, [];
+    VkE8 = 0xE8, 0, 0, 0, [];
+    VkE9 = 0xE9, 0, 0, 0, [];
+    VkEA = 0xEA, 0, 0, 0, [];
+    VkEB = 0xEB, 0, 0, 0, [];
+    VkEC = 0xEC, 0, 0, 0, [];
+    VkED = 0xED, 0, 0, 0, [];
+    VkEE = 0xEE, 0, 0, 0, [];
+    VkEF = 0xEF, 0, 0, 0, [];
+    VkF0 = 0xF0, 0, 0, 0, [];
+    VkF1 = 0xF1, 0, 0, 0, [];
+    VkF2 = 0xF2, 0, 0, 0, [];
+    VkF3 = 0xF3, 0, 0, 0, [];
+    VkF4 = 0xF4, 0, 0, 0, [];
+    VkF5 = 0xF5, 0, 0, 0, [];
+    Attn = 0xF6, 0, 0xFD0E, 0x9A, ["attn"];
+    CrSel = 0xF7, 0, 0xFD1C, 0xA3, ["crsel"];
+    ExSel = 0xF8, 0, 0xFD1D, 0xA4, ["exsel"];
+    ErEof = 0xF9, 0, 0xFD06, 0, ["ereof"];
+    Play = 0xFA, 207, 0xFD16, 0, ["play"];
+    Zoom = 0xFB, 372, 0x1008FF8B, 0, ["zoom"];
+    VkFC = 0xFC, 0, 0, 0, [];
+    Pa1 = 0xFD, 0, 0xFD0A, 0, ["pa1"];
+    OemClear = 0xFE, 0, 0, 0, ["oem_clear"];
+    VkFF = 0xFF, 0, 0, 0, [];
+}
+
+lazy_static! {
+    static ref NAME_TO_KEY: HashMap<&'static str, Key> = KEY_TABLE
+        .iter()
+        .flat_map(|(key, _, _, _, _, names)| names.iter().map(move |name| (*name, *key)))
+        .collect();
+}
+
+// PC set 1 scan codes of the keys whose evdev code isn't one, extended keys
+// are 0xE0xx. Num lock is 0xE045 as on windows. Pause is left out, it is the
+// E1 1D 45 sequence in set 1 and 0x45 alone is num lock there.
+const SCANCODES: [(Key, u16); 51] = [
+    (Key::NumLock, 0xE045),
+    (Key::Kana, 0x70),
+    (Key::Convert, 0x79),
+    (Key::NonConvert, 0x7B),
+    (Key::RCtrl, 0xE01D),
+    (Key::RAlt, 0xE038),
+    (Key::Divide, 0xE035),
+    (Key::PrintScreen, 0xE037),
+    (Key::Home, 0xE047),
+    (Key::Up, 0xE048),
+    (Key::PageUp, 0xE049),
+    (Key::Left, 0xE04B),
+    (Key::Right, 0xE04D),
+    (Key::End, 0xE04F),
+    (Key::Down, 0xE050),
+    (Key::PageDown, 0xE051),
+    (Key::Insert, 0xE052),
+    (Key::Delete, 0xE053),
+    (Key::LWin, 0xE05B),
+    (Key::RWin, 0xE05C),
+    (Key::Apps, 0xE05D),
+    (Key::Sleep, 0xE05F),
+    (Key::F13, 0x64),
+    (Key::F14, 0x65),
+    (Key::F15, 0x66),
+    (Key::F16, 0x67),
+    (Key::F17, 0x68),
+    (Key::F18, 0x69),
+    (Key::F19, 0x6A),
+    (Key::F20, 0x6B),
+    (Key::F21, 0x6C),
+    (Key::F22, 0x6D),
+    (Key::F23, 0x6E),
+    (Key::F24, 0x76),
+    (Key::MediaPrevTrack, 0xE010),
+    (Key::MediaNextTrack, 0xE019),
+    (Key::VolumeMute, 0xE020),
+    (Key::LaunchApp2, 0xE021),
+    (Key::MediaPlayPause, 0xE022),
+    (Key::MediaStop, 0xE024),
+    (Key::VolumeDown, 0xE02E),
+    (Key::VolumeUp, 0xE030),
+    (Key::BrowserHome, 0xE032),
+    (Key::BrowserSearch, 0xE065),
+    (Key::BrowserFavorites, 0xE066),
+    (Key::BrowserRefresh, 0xE067),
+    (Key::BrowserStop, 0xE068),
+    (Key::BrowserForward, 0xE069),
+    (Key::BrowserBack, 0xE06A),
+    (Key::LaunchApp1, 0xE06B),
+    (Key::LaunchMail, 0xE06C),
+];
+
+/// Scan code of the numpad enter key, which has no key of its own
+pub const SCANCODE_NUMPAD_ENTER: u16 = 0xE01C;
+
+// evdev KEY_KPENTER
+const EVDEV_NUMPAD_ENTER: u16 = 96;
+
+/// Linux evdev `KEY_*` code of a PC set 1 scan code
+pub fn scancode_to_evdev(code: u16) -> Option<u16> {
+    if code == SCANCODE_NUMPAD_ENTER {
+        return Some(EVDEV_NUMPAD_ENTER);
+    }
+    Key::from_scancode(code).and_then(Key::to_evdev)
+}
+
+type Entry = (
+    Key,
+    u8,
+    Option<u16>,
+    Option<u32>,
+    Option<u16>,
+    &'static [&'static str],
+);
+
+fn entry(key: Key) -> &'static Entry {
+    // the table is in enum order
+    &KEY_TABLE[key as usize]
+}
+
+// a code of a table row, 0 for none
+const fn code16(code: u16) -> Option<u16> {
+    if code == 0 {
+        None
+    } else {
+        Some(code)
+    }
+}
+
+const fn code32(code: u32) -> Option<u32> {
+    if code == 0 {
+        None
+    } else {
+        Some(code)
+    }
+}
+
+impl Key {
+    /// All keys, in virtual key order
+    pub fn all() -> impl Iterator<Item = Key> {
+        KEY_TABLE.iter().map(|e| e.0)
+    }
+
+    /// Key by name from the key names table, e.g. "pagedown"
+    pub fn from_name(name: &str) -> Option<Key> {
+        NAME_TO_KEY.get(name).copied()
+    }
+
+    /// Main name of the key, `None` for letters, digits and OEM keys which
+    /// are written as the character itself
+    pub fn name(self) -> Option<&'static str> {
+        entry(self).5.first().copied()
+    }
+
+    /// `LShift`, `LCtrl` and `LAlt` for the generic `Shift`, `Ctrl` and `Alt`.
+    ///
+    /// Only windows has generic modifier codes, other platforms use the left key.
+    pub fn sided(self) -> Key {
+        match self {
+            Key::Shift => Key::LShift,
+            Key::Ctrl => Key::LCtrl,
+            Key::Alt => Key::LAlt,
+            key => key,
+        }
+    }
+
+    pub fn from_vk(vk: u8) -> Key {
+        // the table has a row for each virtual key, in order
+        KEY_TABLE[vk as usize].0
+    }
+
+    /// Win32 virtual key code
+    pub fn to_vk(self) -> u8 {
+        entry(self).1
+    }
+
+    pub fn from_evdev(code: u16) -> Option<Key> {
+        KEY_TABLE.iter().find(|e| e.2 == Some(code)).map(|e| e.0)
+    }
+
+    /// Linux evdev `KEY_*` code
+    pub fn to_evdev(self) -> Option<u16> {
+        entry(self.sided()).2
+    }
+
+    pub fn from_keysym(keysym: u32) -> Option<Key> {
+        KEY_TABLE.iter().find(|e| e.3 == Some(keysym)).map(|e| e.0)
+    }
+
+    /// X11 keysym, the unshifted one for character keys
+    pub fn to_keysym(self) -> Option<u32> {
+        entry(self.sided()).3
+    }
+
+    pub fn from_hid(usage: u16) -> Option<Key> {
+        KEY_TABLE.iter().find(|e| e.4 == Some(usage)).map(|e| e.0)
+    }
+
+    /// USB HID usage id on the keyboard/keypad page (0x07)
+    pub fn to_hid(self) -> Option<u16> {
+        entry(self.sided()).4
+    }
+
+    pub fn from_scancode(code: u16) -> Option<Key> {
+        Key::all()
+            .find(|key| key.to_scancode() == Some(code))
+            .map(Key::sided)
+    }
+
+    /// PC set 1 scan code, extended keys are `0xE0xx`, e.g. `0xE048` for up
+    pub fn to_scancode(self) -> Option<u16> {
+        if let Some(&(_, code)) = SCANCODES.iter().find(|(key, _)| *key == self) {
+            return Some(code);
+        }
+        // evdev codes up to F12 are scan codes
+        self.to_evdev().filter(|code| (1..=88).contains(code))
+    }
+
+    pub fn is_shift(self) -> bool {
+        matches!(self, Key::Shift | Key::LShift | Key::RShift)
+    }
+
+    pub fn is_ctrl(self) -> bool {
+        matches!(self, Key::Ctrl | Key::LCtrl | Key::RCtrl)
+    }
+
+    pub fn is_alt(self) -> bool {
+        matches!(self, Key::Alt | Key::LAlt | Key::RAlt)
+    }
+
+    pub fn is_win(self) -> bool {
+        matches!(self, Key::LWin | Key::RWin)
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.name() {
+            Some(name) => write!(f, "{}", name),
+            None => write!(f, "{:?}", self),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn table_in_enum_order() {
+        for (i, e) in KEY_TABLE.iter().enumerate() {
+            assert_eq!(i, e.0 as usize);
+        }
+    }
+
+    #[test]
+    fn vk_round_trip() {
+        for key in Key::all() {
+            assert_eq!(key, Key::from_vk(key.to_vk()));
+        }
+        for vk in 0..=255 {
+            assert_eq!(vk, Key::from_vk(vk).to_vk());
+        }
+        assert_eq!(Key::C, Key::from_vk(b'C'));
+        assert_eq!(Key::Vk07, Key::from_vk(0x07));
+        assert_eq!(None, Key::Vk07.name());
+    }
+
+    #[test]
+    fn platform_round_trip() {
+        for key in Key::all() {
+            let sided = key.sided();
+            if let Some(code) = key.to_evdev() {
+                assert_eq!(Some(sided), Key::from_evdev(code), "{:?}", key);
+            }
+            if let Some(keysym) = key.to_keysym() {
+                assert_eq!(Some(sided), Key::from_keysym(keysym), "{:?}", key);
+            }
+            if let Some(usage) = key.to_hid() {
+                assert_eq!(Some(sided), Key::from_hid(usage), "{:?}", key);
+            }
+        }
+    }
+
+    #[test]
+    fn scancode_round_trip() {
+        let mut codes = Vec::new();
+        for key in Key::all() {
+            let code = match key.to_scancode() {
+                Some(code) => code,
+                None => continue,
+            };
+            assert_eq!(Some(key.sided()), Key::from_scancode(code), "{:?}", key);
+            assert_eq!(key.to_evdev(), scancode_to_evdev(code), "{:?}", key);
+            // set 1 codes up to F12 are the evdev codes
+            if code <= 88 {
+                assert_eq!(Some(code), key.to_evdev(), "{:?}", key);
+            }
+            if key == key.sided() {
+                codes.push(code);
+            }
+        }
+        let count = codes.len();
+        codes.sort_unstable();
+        codes.dedup();
+        assert_eq!(count, codes.len());
+        // no code is a none code
+        assert_eq!(None, Key::from_evdev(0));
+        assert_eq!(None, Key::from_keysym(0));
+        assert_eq!(None, Key::from_hid(0));
+        assert_eq!(None, Key::from_scancode(0));
+    }
+
+    #[test]
+    fn scancodes() {
+        assert_eq!(Some(0x1E), Key::A.to_scancode());
+        assert_eq!(Some(0x2A), Key::Shift.to_scancode());
+        assert_eq!(Some(0x1D), Key::LCtrl.to_scancode());
+        assert_eq!(Some(0xE01D), Key::RCtrl.to_scancode());
+        assert_eq!(Some(0xE038), Key::RAlt.to_scancode());
+        assert_eq!(Some(0xE04B), Key::Left.to_scancode());
+        assert_eq!(Some(0xE053), Key::Delete.to_scancode());
+        assert_eq!(Some(0x53), Key::Decimal.to_scancode());
+        assert_eq!(None, Key::Pause.to_scancode());
+        assert_eq!(Some(0xE045), Key::NumLock.to_scancode());
+        assert_eq!(None, Key::from_scancode(0x45));
+        assert_eq!(None, Key::Attn.to_scancode());
+        assert_eq!(Some(96), scancode_to_evdev(SCANCODE_NUMPAD_ENTER));
+        assert_eq!(Some(28), scancode_to_evdev(0x1C));
+    }
+
+    #[test]
+    fn known_codes() {
+        assert_eq!(Some(29), Key::Ctrl.to_evdev());
+        assert_eq!(Some(0xFF0D), Key::Enter.to_keysym());
+        assert_eq!(Some(0x04), Key::A.to_hid());
+        assert_eq!(Some(Key::Digit1), Key::from_evdev(2));
+        assert_eq!(Some(Key::F24), Key::from_hid(0x73));
+    }
+
+    #[test]
+    fn names() {
+        assert_eq!(Some(Key::Backspace), Key::from_name("bs"));
+        assert_eq!(Some(Key::LWin), Key::from_name("win"));
+        assert_eq!(Some("pagedown"), Key::PageDown.name());
+        assert_eq!("ctrl", Key::Ctrl.to_string());
+        assert_eq!("Semicolon", Key::Semicolon.to_string());
+        assert_eq!(Key::PageDown, "PageDown".parse().unwrap());
+        assert!("pagedown".parse::<Key>().is_err());
+    }
+}
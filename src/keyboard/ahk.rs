@@ -0,0 +1,368 @@
+use super::{
+    balance_keys, char_modifier_keys, char_unit, layout, parse_repeat, push_combination,
+    push_dead_keys_or_unicode, push_key_down, push_key_down_and_up, push_key_up,
+    typed_modifiers_up, KeyInput, KeyState, ParseError, Unit, BALANCE,
+};
+use crate::{
+    key::{Key, SCANCODE_NUMPAD_ENTER},
+    layout::{Layout, Modifiers},
+};
+
+// AutoHotkey key names differing from ours, lowercase
+const AHK_NAMES: [(&str, Key); 21] = [
+    ("escape", Key::Esc),
+    ("control", Key::Ctrl),
+    ("lcontrol", Key::LCtrl),
+    ("rcontrol", Key::RCtrl),
+    ("appskey", Key::Apps),
+    ("capslock", Key::CapsLock),
+    ("scrolllock", Key::ScrollLock),
+    ("pgup", Key::PageUp),
+    ("pgdn", Key::PageDown),
+    ("numpadadd", Key::Add),
+    ("numpadsub", Key::Subtract),
+    ("numpadmult", Key::Multiply),
+    ("numpaddiv", Key::Divide),
+    ("numpaddot", Key::Decimal),
+    ("media_next", Key::MediaNextTrack),
+    ("media_prev", Key::MediaPrevTrack),
+    ("media_stop", Key::MediaStop),
+    ("media_play_pause", Key::MediaPlayPause),
+    ("launch_media", Key::LaunchMediaSelect),
+    ("break", Key::Pause),
+    ("printscreen", Key::PrintScreen),
+];
+
+// AutoHotkey keys without a key of their own, sent as scan codes, lowercase
+const AHK_SCANCODES: [(&str, u16); 1] = [("numpadenter", SCANCODE_NUMPAD_ENTER)];
+
+enum Action {
+    Stroke(u32),
+    Down,
+    Up,
+}
+
+// `^`, `+`, `!` or `#`, with a leading `<` or `>` for the left or right key,
+// and its length
+fn modifier_prefix(s: &str) -> Option<(Key, usize)> {
+    let mut chars = s.chars();
+    let (side, symbol) = match chars.next()? {
+        side @ ('<' | '>') => (Some(side), chars.next()?),
+        symbol => (None, symbol),
+    };
+    let key = match (side, symbol) {
+        (None, '^') => Key::Ctrl,
+        (None, '+') => Key::Shift,
+        (None, '!') => Key::Alt,
+        (None, '#') => Key::LWin,
+        (Some('<'), '^') => Key::LCtrl,
+        (Some('>'), '^') => Key::RCtrl,
+        (Some('<'), '+') => Key::LShift,
+        (Some('>'), '+') => Key::RShift,
+        (Some('<'), '!') => Key::LAlt,
+        (Some('>'), '!') => Key::RAlt,
+        (Some('<'), '#') => Key::LWin,
+        (Some('>'), '#') => Key::RWin,
+        _ => return None,
+    };
+    Some((key, if side.is_some() { 2 } else { 1 }))
+}
+
+// the name of `{Name}`, like `Enter`, `vk41`, `U+20AC` or a char
+fn unit_of(name: &str, layout: Option<&Layout>) -> Option<Unit> {
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Some(char_unit(c, layout));
+    }
+
+    let lower = name.to_ascii_lowercase();
+    if let Some(hex) = lower.strip_prefix("u+") {
+        let c = u32::from_str_radix(hex, 16).ok().and_then(char::from_u32)?;
+        return Some(Unit::Unicode(c));
+    }
+    if let Some(code) = lower.strip_prefix("vk") {
+        // like vk41 or vk41sc01e, the scan code is left out
+        let hex = code.split("sc").next().unwrap();
+        let vk = u8::from_str_radix(hex, 16).ok()?;
+        return Some(Unit::Key(Key::from_vk(vk), Modifiers::NONE));
+    }
+    AHK_NAMES
+        .iter()
+        .find(|(ahk_name, _)| *ahk_name == lower)
+        .map(|&(_, key)| key)
+        .or_else(|| Key::from_name(&lower))
+        .map(|key| Unit::Key(key, Modifiers::NONE))
+}
+
+fn push_unit(
+    unit: Unit,
+    action: Action,
+    prefix: &[Key],
+    layout: Option<&Layout>,
+    inputs: &mut Vec<KeyInput>,
+    state: &mut KeyState,
+) {
+    let (key, modifiers) = match unit {
+        Unit::Key(key, modifiers) => (key, modifiers),
+        Unit::Unicode(c) => {
+            // modifiers of a char not on a key of its own are ignored
+            if let Action::Stroke(count) = action {
+                for _ in 0..count {
+                    push_dead_keys_or_unicode(c, layout, inputs, state);
+                }
+            }
+            return;
+        }
+    };
+
+    match action {
+        // like the chars of a key string, the modifiers typed for the last
+        // char are released by a key needing none
+        Action::Stroke(count) if prefix.is_empty() => {
+            for _ in 0..count {
+                push_key_down_and_up(key, modifiers, inputs, state);
+            }
+        }
+        // like <ctrl+shift+a*3>
+        Action::Stroke(count) => {
+            typed_modifiers_up(inputs, state);
+            let mut mod_keys = prefix.to_vec();
+            mod_keys.extend(char_modifier_keys(modifiers, prefix));
+            push_combination(&mod_keys, key, count, None, inputs, state);
+        }
+        // like <ctrl+> or <ctrl->, wrapped in the prefix modifiers
+        Action::Down | Action::Up => {
+            typed_modifiers_up(inputs, state);
+            for &mod_key in prefix {
+                push_key_down(mod_key, inputs, state);
+            }
+            if let Action::Down = action {
+                push_key_down(key, inputs, state);
+            } else {
+                push_key_up(key, inputs, state);
+            }
+            for &mod_key in prefix.iter().rev() {
+                push_key_up(mod_key, inputs, state);
+            }
+        }
+    }
+}
+
+// like push_unit, for a key known by its scan code only
+fn push_scancode(
+    code: u16,
+    action: Action,
+    prefix: &[Key],
+    inputs: &mut Vec<KeyInput>,
+    state: &mut KeyState,
+) {
+    typed_modifiers_up(inputs, state);
+    for &mod_key in prefix {
+        push_key_down(mod_key, inputs, state);
+    }
+    match action {
+        Action::Stroke(count) => {
+            for _ in 0..count {
+                inputs.push(KeyInput::ScanCodeDown(code));
+                inputs.push(KeyInput::ScanCodeUp(code));
+            }
+        }
+        Action::Down => inputs.push(KeyInput::ScanCodeDown(code)),
+        Action::Up => inputs.push(KeyInput::ScanCodeUp(code)),
+    }
+    for &mod_key in prefix.iter().rev() {
+        push_key_up(mod_key, inputs, state);
+    }
+}
+
+// `Down`, `Up`, a repeat count or nothing after the name in braces
+fn parse_action(arg: &str) -> Option<Action> {
+    match arg.to_ascii_lowercase().as_str() {
+        "" => Some(Action::Stroke(1)),
+        "down" | "downtemp" | "downr" => Some(Action::Down),
+        "up" => Some(Action::Up),
+        count => parse_repeat(count).map(Action::Stroke),
+    }
+}
+
+/// Parse an AutoHotkey `Send` string, like `^c`, `+{Tab 3}` or `{Ctrl down}`,
+/// into the key inputs of the matching key string
+pub fn parse_ahk(s: &str) -> std::result::Result<Vec<KeyInput>, ParseError> {
+    let layout = layout();
+    let mut inputs = parse_ahk_with(s, layout.as_deref())?;
+    let balance = *BALANCE.read().unwrap();
+    balance_keys(s, &mut inputs, balance, layout.as_deref())?;
+    Ok(inputs)
+}
+
+// parse with `layout`, None for the system layout
+pub(super) fn parse_ahk_with(
+    s: &str,
+    layout: Option<&Layout>,
+) -> std::result::Result<Vec<KeyInput>, ParseError> {
+    let mut result: Vec<KeyInput> = Vec::new();
+    let mut state = KeyState::new();
+    // modifiers of the next key, like `^+` of `^+s`
+    let mut prefix: Vec<Key> = Vec::new();
+    // after {Raw} or {Text}, the rest is plain text
+    let mut raw = false;
+
+    let mut pos = 0;
+    while let Some(c) = s[pos..].chars().next() {
+        if raw {
+            push_unit(
+                char_unit(c, layout),
+                Action::Stroke(1),
+                &[],
+                layout,
+                &mut result,
+                &mut state,
+            );
+            pos += c.len_utf8();
+            continue;
+        }
+
+        if let Some((key, len)) = modifier_prefix(&s[pos..]) {
+            prefix.push(key);
+            pos += len;
+            continue;
+        }
+
+        if c != '{' {
+            let unit = char_unit(c, layout);
+            push_unit(
+                unit,
+                Action::Stroke(1),
+                &prefix,
+                layout,
+                &mut result,
+                &mut state,
+            );
+            prefix.clear();
+            pos += c.len_utf8();
+            continue;
+        }
+
+        // like {Enter}, {Tab 3}, {Ctrl down}, or {}} for '}'
+        let start = pos + 1;
+        let first_len = s[start..].chars().next().map_or(0, char::len_utf8);
+        let end = match s[start + first_len..].find('}') {
+            Some(end) => start + first_len + end,
+            None => return Err(ParseError::new(s, s.len(), "", "'}'")),
+        };
+        let content = &s[start..end];
+        pos = end + 1;
+
+        match content.to_ascii_lowercase().as_str() {
+            "raw" | "text" => {
+                raw = true;
+                continue;
+            }
+            "blind" => continue,
+            _ => {}
+        }
+
+        let (name, arg, arg_start) = match content[first_len..].find(' ') {
+            Some(space) => {
+                let arg_start = first_len + space + 1;
+                (
+                    &content[..first_len + space],
+                    &content[arg_start..],
+                    arg_start,
+                )
+            }
+            None => (content, "", content.len()),
+        };
+        let bad_action = || {
+            ParseError::new(
+                s,
+                start + arg_start,
+                arg,
+                "repeat count from 1 to 10000, 'down' or 'up'",
+            )
+        };
+        let lower = name.to_ascii_lowercase();
+        if let Some(&(_, code)) = AHK_SCANCODES
+            .iter()
+            .find(|(ahk_name, _)| *ahk_name == lower)
+        {
+            let action = parse_action(arg.trim()).ok_or_else(bad_action)?;
+            push_scancode(code, action, &prefix, &mut result, &mut state);
+            prefix.clear();
+            continue;
+        }
+        let unit = unit_of(name, layout)
+            .ok_or_else(|| ParseError::new(s, start, name, "key name, char or U+ code point"))?;
+        let action = parse_action(arg.trim()).ok_or_else(bad_action)?;
+        push_unit(unit, action, &prefix, layout, &mut result, &mut state);
+        prefix.clear();
+    }
+    if !prefix.is_empty() {
+        return Err(ParseError::new(s, s.len(), "", "key after modifier"));
+    }
+    typed_modifiers_up(&mut result, &mut state);
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keyboard::parse_str_with;
+
+    // both parse to the same inputs
+    fn assert_same(ahk: &str, native: &str, layout: &Layout) {
+        assert_eq!(
+            parse_str_with(native, Some(layout)).unwrap(),
+            parse_ahk_with(ahk, Some(layout)).unwrap(),
+            "{} and {}",
+            ahk,
+            native
+        );
+    }
+
+    #[test]
+    fn same_as_key_strings() {
+        let us = Layout::builtin("us").unwrap();
+        for (ahk, native) in [
+            ("^c", "<ctrl+c>"),
+            ("+{Tab 3}", "<shift+tab*3>"),
+            ("{Enter}", "<enter>"),
+            ("!{F4}", "<alt+f4>"),
+            ("#r", "<win+r>"),
+            ("{Ctrl down}ab{Ctrl up}", "<ctrl+>ab<ctrl->"),
+            ("Hello World{!}", "Hello World!"),
+            ("^A", "<ctrl+shift+a>"),
+            ("+A", "<shift+a>"),
+            ("{A 2}x", "AAx"),
+            ("a{Home}B", "a<home>B"),
+            ("{{}{}}{^}{+}{!}{#}", "{}^+!#"),
+            ("<^>!e", "<lctrl+ralt+e>"),
+            ("a < b", "a <lt> b"),
+            ("{U+4e2d}{vk41}", "<u:4e2d><65>"),
+            ("{Blind}{Text}^c{Tab}", "^c{Tab}"),
+            ("{PgDn}{Escape}{BS 2}", "<pagedown><esc><bs*2>"),
+            ("{NumpadEnter}{Enter}", "<sc:0xe01c><enter>"),
+            ("^{NumpadEnter 2}", "<ctrl+><sc:0xe01c*2><ctrl->"),
+            ("{NumpadEnter down}", "<sc:0xe01c+>"),
+        ] {
+            assert_same(ahk, native, &us);
+        }
+        let de = Layout::builtin("de").unwrap();
+        assert_same("^{@}@", "<ctrl+ralt+q>@", &de);
+    }
+
+    #[test]
+    fn ahk_errors() {
+        let us = Layout::builtin("us").unwrap();
+        let err = |s: &str| parse_ahk_with(s, Some(&us)).unwrap_err();
+
+        let e = err("a{Entr}");
+        assert_eq!((2, "Entr"), (e.offset, e.fragment.as_str()));
+        let e = err("{Tab often}");
+        assert_eq!((5, "often"), (e.offset, e.fragment.as_str()));
+        let e = err("{a 4294967295}");
+        assert_eq!((3, "4294967295"), (e.offset, e.fragment.as_str()));
+        assert_eq!("expected '}', found end of input", err("{Tab").to_string());
+        assert_eq!("key after modifier", err("x^").expected);
+    }
+}
@@ -0,0 +1,133 @@
+use std::{collections::HashMap, sync::RwLock};
+
+use super::{
+    layout, mouse_tokens, parse_key_name, parse_tokens_with, KeyState, ESCAPED_KEY_NAME_TO_CHAR,
+};
+use crate::{
+    errors::Result,
+    key::Key,
+    layout::{Layout, Modifiers},
+};
+
+/// Alias names and the key strings they expand to
+pub(super) type Aliases = HashMap<String, String>;
+
+lazy_static! {
+    pub(super) static ref ALIASES: RwLock<Aliases> = RwLock::new(HashMap::new());
+}
+
+// letters, digits and '_', not a char, key name or virtual key code
+fn valid_name(name: &str) -> bool {
+    name.chars().count() > 1
+        && name.chars().all(|c| c.is_alphanumeric() || c == '_')
+        && !name.chars().all(|c| c.is_ascii_digit())
+        && Key::from_name(name).is_none()
+        && !ESCAPED_KEY_NAME_TO_CHAR.contains_key(name)
+        && !mouse_tokens::is_mouse_name(name)
+}
+
+// parse the keys of `name` in `aliases`, from no key held
+fn check(name: &str, aliases: &Aliases, layout: Option<&Layout>) -> Result<()> {
+    let mut expanding = vec![name.to_string()];
+    parse_tokens_with(
+        &aliases[name],
+        layout,
+        aliases,
+        &mut expanding,
+        &mut KeyState::new(),
+        &mut Vec::new(),
+    )?;
+    Ok(())
+}
+
+/// Register `<name>` for `keys` in key strings, like `ok` for `<enter>` or
+/// `copy` for `<ctrl+c>`, replacing an alias of the same name. Aliases are
+/// keys only, without mouse tokens.
+///
+/// Aliases of a single key can be used in combinations, like `<shift+ok>`,
+/// others can be repeated, like `<copy*2>`. `keys` may use other aliases, an
+/// alias expanding to itself is an error.
+pub fn set_alias(name: &str, keys: &str) -> Result<()> {
+    if !valid_name(name) {
+        bail!(
+            "invalid alias name {:?}, expected letters, digits or '_', not a key name",
+            name
+        );
+    }
+    let layout = layout();
+    let mut aliases = ALIASES.write().unwrap();
+    let mut updated = aliases.clone();
+    updated.insert(name.to_string(), keys.to_string());
+    check(name, &updated, layout.as_deref())?;
+    // the aliases using `name` may now expand to themselves
+    for other in updated.keys().filter(|other| *other != name) {
+        if let Err(e) = check(other, &updated, layout.as_deref()) {
+            bail!("alias {} would be invalid: {}", other, e);
+        }
+    }
+    *aliases = updated;
+    Ok(())
+}
+
+/// Remove the alias `name`, return whether it was registered
+pub fn remove_alias(name: &str) -> bool {
+    ALIASES.write().unwrap().remove(name).is_some()
+}
+
+// the key of an alias of a single key token, like `<enter>` or `<ok>` for an
+// alias `ok` of one, None otherwise. Text like `x` types chars, it is no key.
+pub(super) fn alias_key(
+    name: &str,
+    layout: Option<&Layout>,
+    aliases: &Aliases,
+    expanding: &mut Vec<String>,
+) -> Option<(Key, Modifiers)> {
+    if expanding.iter().any(|n| n == name) {
+        return None;
+    }
+    let keys = aliases.get(name)?;
+    let inner = keys.strip_prefix('<')?.strip_suffix('>')?;
+    if let Ok(key) = parse_key_name(inner, layout) {
+        return Some(key);
+    }
+    expanding.push(name.to_string());
+    let key = alias_key(inner, layout, aliases, expanding);
+    expanding.pop();
+    key
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn alias_names() {
+        assert!(valid_name("ok"));
+        assert!(valid_name("copy_all2"));
+        assert!(!valid_name("x"));
+        assert!(!valid_name("enter"));
+        assert!(!valid_name("lt"));
+        assert!(!valid_name("42"));
+        assert!(!valid_name("a+b"));
+    }
+
+    #[test]
+    fn single_key_aliases() {
+        let aliases: Aliases = HashMap::from([
+            ("ok".to_string(), "<enter>".to_string()),
+            ("yes".to_string(), "<ok>".to_string()),
+            ("sure".to_string(), "ok".to_string()),
+            ("x".to_string(), "x".to_string()),
+            ("copy".to_string(), "<ctrl+c>".to_string()),
+            ("loop".to_string(), "<loop>".to_string()),
+        ]);
+        let key = |name: &str| alias_key(name, None, &aliases, &mut Vec::new());
+        assert_eq!(Some((Key::Enter, Modifiers::NONE)), key("ok"));
+        assert_eq!(Some((Key::Enter, Modifiers::NONE)), key("yes"));
+        // plain text, typed as chars
+        assert_eq!(None, key("sure"));
+        assert_eq!(None, key("x"));
+        assert_eq!(None, key("copy"));
+        assert_eq!(None, key("loop"));
+    }
+}
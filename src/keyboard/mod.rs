@@ -0,0 +1,1640 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, RwLock,
+    },
+    time::Duration,
+};
+#[cfg(windows)]
+use std::{collections::HashSet, thread::sleep};
+
+#[cfg(windows)]
+use windows::{
+    core::PCSTR,
+    Win32::{
+        Foundation::HWND,
+        System::DataExchange::GlobalAddAtomA,
+        UI::{
+            Input::KeyboardAndMouse::{
+                GetAsyncKeyState, MapVirtualKeyW, RegisterHotKey, UnregisterHotKey, VkKeyScanW,
+                HOT_KEY_MODIFIERS, MOD_ALT, MOD_CONTROL, MOD_SHIFT, MOD_WIN,
+            },
+            WindowsAndMessaging::{GetMessageA, MSG, WM_HOTKEY},
+        },
+    },
+};
+
+use crate::{
+    backend::{self, Input, Played, Timeline},
+    errors::{Error, ErrorKind, Result},
+    key::Key,
+    layout::{Layout, Modifiers},
+};
+
+mod ahk;
+mod alias;
+mod mouse_tokens;
+mod render;
+mod typing;
+mod vim;
+mod xdo;
+
+pub use ahk::parse_ahk;
+pub use alias::{remove_alias, set_alias};
+pub use render::render;
+pub use typing::{typing_schedule, TypingOptions};
+pub use vim::{parse_vim, set_vim_leaders, VimLeaders};
+pub use xdo::parse_xdo;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyInput {
+    KeyDown(Key),
+    KeyUp(Key),
+    /// type a char directly, whatever the layout is, e.g. CJK text or emoji
+    Unicode(char),
+    /// wait before sending the next inputs
+    Delay(Duration),
+    /// a PC set 1 scan code, extended keys are `0xE0xx`, see `Key::to_scancode`
+    ScanCodeDown(u16),
+    ScanCodeUp(u16),
+}
+
+pub fn input_keys(key_inputs: &[KeyInput]) -> Result<()> {
+    let inputs: Vec<Input> = key_inputs.iter().map(|k| Input::Key(*k)).collect();
+    send_inputs(&inputs)
+}
+
+// keys by scan code in scancode mode
+fn mode_inputs(inputs: &[Input]) -> Vec<Input> {
+    let scancode_mode = SCANCODE_MODE.load(Ordering::Relaxed);
+    inputs
+        .iter()
+        .map(|input| match input {
+            Input::Key(k) if scancode_mode => Input::Key(scancode_input(k).unwrap_or(*k)),
+            _ => *input,
+        })
+        .collect()
+}
+
+// send key and mouse inputs in order
+fn send_inputs(inputs: &[Input]) -> Result<()> {
+    backend::input(&mode_inputs(inputs))
+}
+
+// the key input by scan code, None when it has none
+fn scancode_input(input: &KeyInput) -> Option<KeyInput> {
+    match input {
+        KeyInput::KeyDown(key) => key.to_scancode().map(KeyInput::ScanCodeDown),
+        KeyInput::KeyUp(key) => key.to_scancode().map(KeyInput::ScanCodeUp),
+        _ => None,
+    }
+}
+
+/// Send keys by scan code instead of virtual key, for games reading
+/// DirectInput or raw input and remote desktop windows. Keys without a scan
+/// code are still sent by virtual key.
+pub fn set_scancode_mode(enabled: bool) {
+    SCANCODE_MODE.store(enabled, Ordering::Relaxed);
+}
+
+/// Set the layout used to type chars, `None` for the system layout.
+///
+/// The system layout is the active one on windows (`VkKeyScanW`) and US
+/// elsewhere. Keys of a layout are physical positions, note the win32 backend
+/// sends virtual keys, so there it should match the active layout.
+pub fn set_layout(layout: Option<Layout>) {
+    *LAYOUT.write().unwrap() = layout.map(Arc::new);
+}
+
+/// The layout set by `set_layout`
+pub fn layout() -> Option<Arc<Layout>> {
+    LAYOUT.read().unwrap().clone()
+}
+
+/// What parsing does with keys still held at the end of a key string,
+/// e.g. ctrl after `<ctrl+>abc`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Balance {
+    /// leave them down
+    Keep = 0,
+    /// fail to parse
+    Error = 1,
+    /// release them, last pressed first
+    Release = 2,
+}
+
+impl Balance {
+    pub fn from_i32(i: i32) -> Option<Balance> {
+        match i {
+            0 => Some(Balance::Keep),
+            1 => Some(Balance::Error),
+            2 => Some(Balance::Release),
+            _ => None,
+        }
+    }
+}
+
+/// Set what parsing does with keys left held, `Balance::Keep` by default
+pub fn set_balance(balance: Balance) {
+    *BALANCE.write().unwrap() = balance;
+}
+
+/// Send a key string, mouse tokens like `<click:300,400>` included, as one
+/// ordered batch
+pub fn input(s: &str) -> Result<()> {
+    let inputs = parse_input(s)?;
+    send_inputs(&inputs)
+}
+
+/// Send a key string like `input`, waiting between keys as set by `options`
+pub fn input_with(s: &str, options: &TypingOptions) -> Result<()> {
+    let inputs = parse_input(s)?;
+    send_inputs(&typing_schedule(&inputs, options))
+}
+
+/// Send a key string like `input`, each key at its offset from the start
+/// given by the delays, return when each was actually sent
+pub fn input_timed(s: &str) -> Result<Vec<Played>> {
+    let inputs = parse_input(s)?;
+    Timeline::from_inputs(&mode_inputs(&inputs)).play()
+}
+
+/// Send an AutoHotkey `Send` string, like `^c`, `+{Tab 3}` or `{Ctrl down}`
+pub fn input_ahk(s: &str) -> Result<()> {
+    let inputs = parse_ahk(s)?;
+    input_keys(&inputs)
+}
+
+/// Send xdotool key chords, like `ctrl+alt+t super+Return`
+pub fn input_xdo(s: &str) -> Result<()> {
+    let inputs = parse_xdo(s)?;
+    input_keys(&inputs)
+}
+
+/// Send vim key notation, like `<C-w>v<Leader>f<CR>`
+pub fn input_vim(s: &str) -> Result<()> {
+    let inputs = parse_vim(s)?;
+    input_keys(&inputs)
+}
+
+#[cfg(windows)]
+pub fn wait_keys_up(s: &str) -> Result<()> {
+    let inputs = parse_str(s)?;
+    let mut keys: HashSet<Key> = HashSet::new();
+    for input in inputs {
+        match input {
+            KeyInput::KeyDown(key) => keys.insert(key),
+            KeyInput::KeyUp(key) => keys.insert(key),
+            KeyInput::ScanCodeDown(code) | KeyInput::ScanCodeUp(code) => {
+                match Key::from_scancode(code) {
+                    Some(key) => keys.insert(key),
+                    None => false,
+                }
+            }
+            KeyInput::Unicode(_) | KeyInput::Delay(_) => false,
+        };
+    }
+    let mut count = 0i32;
+    for key in keys {
+        loop {
+            let ret = unsafe { GetAsyncKeyState(key.to_vk().into()) };
+            if (ret as u16 & 0x8000) == 0 {
+                break;
+            }
+            // key not up
+            sleep(Duration::from_millis(1));
+            count += 1;
+            if count > 30000 {
+                return Err("Wait longer than 30 seconds".into());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(windows)]
+pub fn hotkey_register(hotkey: &str) -> Result<i32> {
+    let inputs = parse_str(hotkey)?;
+    let mut state = KeyState::new();
+
+    let mut modifiers: HOT_KEY_MODIFIERS = HOT_KEY_MODIFIERS(0);
+    let mut vk = 0u32;
+
+    for input in inputs {
+        if let KeyInput::KeyDown(key) = input {
+            if key.is_shift() {
+                if state.shift.is_some() {
+                    bail!("Invalid hotkey");
+                }
+                modifiers.0 |= MOD_SHIFT.0;
+                state.shift = Some(key);
+            } else if key.is_ctrl() {
+                if state.ctrl.is_some() {
+                    bail!("Invalid hotkey");
+                }
+                modifiers.0 |= MOD_CONTROL.0;
+                state.ctrl = Some(key);
+            } else if key.is_alt() {
+                if state.alt.is_some() {
+                    bail!("Invalid hotkey");
+                }
+                modifiers.0 |= MOD_ALT.0;
+                state.alt = Some(key);
+            } else if key.is_win() {
+                if state.win.is_some() {
+                    bail!("Invalid hotkey");
+                }
+                modifiers.0 |= MOD_WIN.0;
+                state.win = Some(key);
+            } else {
+                if vk != 0 {
+                    bail!("Invalid hotkey");
+                }
+                vk = key.to_vk().into();
+            }
+        } else {
+            break;
+        }
+    }
+
+    if vk == 0 {
+        bail!("Invalid hotkey");
+    }
+
+    let atom = unsafe { GlobalAddAtomA(PCSTR(hotkey.as_ptr())) };
+    if atom == 0 {
+        bail!(Error::with_chain(
+            std::io::Error::last_os_error(),
+            "GlobalAddAtomA() failed"
+        ));
+    }
+
+    let ret = unsafe { RegisterHotKey(HWND(0), atom.into(), modifiers, vk) };
+    if !ret.as_bool() {
+        bail!(Error::with_chain(
+            std::io::Error::last_os_error(),
+            "RegisterHotKey() failed"
+        ));
+    }
+
+    Ok(atom.into())
+}
+
+#[cfg(windows)]
+pub fn hotkey_unregister(id: i32) {
+    unsafe { UnregisterHotKey(HWND(0), id) };
+}
+
+#[cfg(windows)]
+pub fn hotkey_wait() -> Result<i32> {
+    let mut msg: MSG = unsafe { std::mem::zeroed() };
+    while unsafe { GetMessageA(&mut msg, HWND(0), 0, 0) }.as_bool() {
+        if msg.message == WM_HOTKEY {
+            return Ok(msg.wParam.0 as _);
+        }
+    }
+    bail!("Unknown!");
+}
+
+static SCANCODE_MODE: AtomicBool = AtomicBool::new(false);
+
+lazy_static! {
+    static ref LAYOUT: RwLock<Option<Arc<Layout>>> = RwLock::new(None);
+    static ref BALANCE: RwLock<Balance> = RwLock::new(Balance::Keep);
+    static ref ESCAPED_KEY_NAME_TO_CHAR: HashMap<&'static str, char> =
+        HashMap::from([("lt", '<'), ("gt", '>'), ("minus", '-'), ("plus", '+')]);
+}
+
+/// Error of parsing a key string
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    /// the parsed key string
+    pub input: String,
+    /// byte offset of `fragment` in `input`
+    pub offset: usize,
+    /// the offending part of `input`, empty at the end of input
+    pub fragment: String,
+    /// what was expected instead
+    pub expected: String,
+}
+
+impl ParseError {
+    fn new(input: &str, offset: usize, fragment: &str, expected: &str) -> ParseError {
+        ParseError {
+            input: input.to_string(),
+            offset,
+            fragment: fragment.to_string(),
+            expected: expected.to_string(),
+        }
+    }
+
+    /// 1-based char column of the error
+    pub fn column(&self) -> usize {
+        self.input[..self.offset].chars().count() + 1
+    }
+
+    /// The message followed by the input, with carets under the fragment:
+    ///
+    /// ```text
+    /// expected key name, char or virtual key code, found "ctlr" at column 2
+    /// <ctlr+c>
+    ///  ^^^^
+    /// ```
+    pub fn render(&self) -> String {
+        let carets = self.fragment.chars().count().max(1);
+        format!(
+            "{}\n{}\n{}{}",
+            self,
+            self.input,
+            " ".repeat(self.column() - 1),
+            "^".repeat(carets)
+        )
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.fragment.is_empty() {
+            write!(f, "expected {}, found end of input", self.expected)
+        } else {
+            write!(
+                f,
+                "expected {}, found {:?} at column {}",
+                self.expected,
+                self.fragment,
+                self.column()
+            )
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl From<ParseError> for Error {
+    fn from(e: ParseError) -> Error {
+        ErrorKind::Parse(e).into()
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct KeyState {
+    // None means not pressed, otherwise the pressed key, e.g. LShift
+    shift: Option<Key>,
+    ctrl: Option<Key>,
+    alt: Option<Key>,
+    win: Option<Key>,
+    altgr: Option<Key>,
+    // modifiers pressed to type chars, released when no longer needed
+    typed: Modifiers,
+}
+
+impl KeyState {
+    fn new() -> KeyState {
+        KeyState {
+            shift: None,
+            ctrl: None,
+            alt: None,
+            win: None,
+            altgr: None,
+            typed: Modifiers::NONE,
+        }
+    }
+
+    fn modifier(&mut self, modifier: Modifiers) -> &mut Option<Key> {
+        match modifier {
+            Modifiers::SHIFT => &mut self.shift,
+            Modifiers::CTRL => &mut self.ctrl,
+            Modifiers::ALT => &mut self.alt,
+            Modifiers::ALTGR => &mut self.altgr,
+            _ => unreachable!("not a single modifier {:?}", modifier),
+        }
+    }
+}
+
+// modifiers of chars and the keys pressed for them, in press order
+const CHAR_MODIFIERS: [(Modifiers, Key); 4] = [
+    (Modifiers::CTRL, Key::Ctrl),
+    (Modifiers::ALT, Key::Alt),
+    (Modifiers::ALTGR, Key::RAlt),
+    (Modifiers::SHIFT, Key::Shift),
+];
+
+lazy_static! {
+    static ref US_LAYOUT: Layout = Layout::builtin("us").unwrap();
+}
+
+#[cfg(windows)]
+fn parse_str(s: &str) -> Result<Vec<KeyInput>> {
+    Ok(parse(s)?)
+}
+
+/// Parse a key string into key inputs, see `exports::kb_input` for the syntax
+pub fn parse(s: &str) -> std::result::Result<Vec<KeyInput>, ParseError> {
+    let layout = layout();
+    let mut inputs = parse_str_with(s, layout.as_deref())?;
+    let balance = *BALANCE.read().unwrap();
+    balance_keys(s, &mut inputs, balance, layout.as_deref())?;
+    Ok(inputs)
+}
+
+/// Parse a key string with mouse tokens, like `<click:300,400>hi<enter>`,
+/// into key and mouse inputs in order, see `exports::kb_input` for the syntax
+pub fn parse_input(s: &str) -> std::result::Result<Vec<Input>, ParseError> {
+    let layout = layout();
+    let mut inputs = mouse_tokens::parse_input_with(s, layout.as_deref())?;
+    let mut keys: Vec<KeyInput> = inputs
+        .iter()
+        .filter_map(|input| match input {
+            Input::Key(key_input) => Some(*key_input),
+            Input::Mouse(_) => None,
+        })
+        .collect();
+    let parsed = keys.len();
+    let balance = *BALANCE.read().unwrap();
+    balance_keys(s, &mut keys, balance, layout.as_deref())?;
+    inputs.extend(keys[parsed..].iter().map(|k| Input::Key(*k)));
+    Ok(inputs)
+}
+
+// the release of a key or scan code down
+fn release_of(input: &KeyInput) -> Option<KeyInput> {
+    match input {
+        KeyInput::KeyDown(key) => Some(KeyInput::KeyUp(*key)),
+        KeyInput::ScanCodeDown(code) => Some(KeyInput::ScanCodeUp(*code)),
+        _ => None,
+    }
+}
+
+// releases of the keys pressed down and not released by `inputs`, in press
+// order
+fn held_keys(inputs: &[KeyInput]) -> Vec<KeyInput> {
+    let mut held: Vec<KeyInput> = Vec::new();
+    for input in inputs {
+        match release_of(input) {
+            Some(release) if !held.contains(&release) => held.push(release),
+            Some(_) => {}
+            None => held.retain(|release| release != input),
+        }
+    }
+    held
+}
+
+// check or release the keys left held at the end of `s`
+fn balance_keys(
+    s: &str,
+    inputs: &mut Vec<KeyInput>,
+    balance: Balance,
+    layout: Option<&Layout>,
+) -> std::result::Result<(), ParseError> {
+    let held = held_keys(inputs);
+    match balance {
+        Balance::Keep => {}
+        Balance::Error => {
+            let name = match held.first() {
+                Some(&KeyInput::KeyUp(key)) => {
+                    render::bracket_name(key, layout.unwrap_or(&US_LAYOUT))
+                }
+                Some(&KeyInput::ScanCodeUp(code)) => format!("sc:{:#x}", code),
+                _ => return Ok(()),
+            };
+            let expected = format!("'<{}->'", name);
+            return Err(ParseError::new(s, s.len(), "", &expected));
+        }
+        Balance::Release => inputs.extend(held.into_iter().rev()),
+    }
+    Ok(())
+}
+
+// parse with `layout`, None for the system layout
+fn parse_str_with(
+    s: &str,
+    layout: Option<&Layout>,
+) -> std::result::Result<Vec<KeyInput>, ParseError> {
+    let mut result: Vec<KeyInput> = Vec::new();
+    let mut state = KeyState::new();
+    parse_tokens(s, layout, &mut state, &mut result)?;
+    typed_modifiers_up(&mut result, &mut state);
+    Ok(result)
+}
+
+// parse `s` from `state`, the modifiers typed for the last chars are left
+// down, to be released by what follows
+fn parse_tokens(
+    s: &str,
+    layout: Option<&Layout>,
+    state: &mut KeyState,
+    result: &mut Vec<KeyInput>,
+) -> std::result::Result<(), ParseError> {
+    let aliases = alias::ALIASES.read().unwrap();
+    parse_tokens_with(s, layout, &aliases, &mut Vec::new(), state, result)
+}
+
+// parse with `aliases`, `expanding` are the aliases being expanded
+fn parse_tokens_with(
+    s: &str,
+    layout: Option<&Layout>,
+    aliases: &alias::Aliases,
+    expanding: &mut Vec<String>,
+    state: &mut KeyState,
+    result: &mut Vec<KeyInput>,
+) -> std::result::Result<(), ParseError> {
+    let mut in_bracket = false;
+    let mut part = String::new();
+    // byte offsets of the current '<' and name
+    let mut bracket_start = 0;
+    let mut part_start = 0;
+
+    let mut key_mods_vec: Vec<(Key, Modifiers)> = Vec::new();
+    let mut seps: Vec<bool> = Vec::new();
+
+    // the '>' of <sc:0x1e+> or <sc:0x1e->, already handled
+    let mut skip_close = false;
+
+    for (i, c) in s.char_indices() {
+        if skip_close {
+            skip_close = false;
+            continue;
+        }
+        if !in_bracket {
+            if c == '<' {
+                in_bracket = true;
+                bracket_start = i;
+                typed_modifiers_up(result, state);
+                key_mods_vec.clear();
+                seps.clear();
+                continue;
+            }
+
+            match parse_char(c, layout) {
+                Ok((key, modifiers)) => push_key_down_and_up(key, modifiers, result, state),
+                // not on a key of its own
+                Err(_) => push_dead_keys_or_unicode(c, layout, result, state),
+            }
+            continue;
+        }
+
+        // in bracket
+        if c != '>' && c != '-' && c != '+' {
+            // normal char
+            if part.is_empty() {
+                part_start = i;
+            }
+            part.push(c);
+            continue;
+        }
+
+        let mut repeat = 1;
+        let mut hold = None;
+        if c == '>' {
+            if let Some(star) = suffix_start(&part, '*') {
+                // like <tab*5>
+                let count = &part[star + 1..];
+                repeat = parse_repeat(count)
+                    .ok_or_else(|| ParseError::new(s, part_start + star + 1, count, REPEAT))?;
+                part.truncate(star);
+            }
+            if let Some(tilde) = suffix_start(&part, '~') {
+                // like <space~500ms>
+                let text = &part[tilde + 1..];
+                let duration = parse_duration(text).ok_or_else(|| {
+                    let offset = part_start + tilde + 1;
+                    ParseError::new(s, offset, text, "hold duration like 500, 500ms or 2s")
+                })?;
+                hold = Some(duration);
+                part.truncate(tilde);
+            }
+        }
+
+        if let Some(code) = part.strip_prefix("sc:") {
+            // like <sc:0x1e>, <sc:0xe048~50ms*2> or <sc:0x1e+>
+            let scancode = parse_scancode(code).ok_or_else(|| {
+                ParseError::new(s, part_start + 3, code, "scan code like 0x1e or 0xe048")
+            })?;
+            if !key_mods_vec.is_empty() || (c != '>' && !s[i + 1..].starts_with('>')) {
+                let end = s[i..].find('>').map_or(s.len(), |end| i + end + 1);
+                let bracket = &s[bracket_start..end];
+                return Err(ParseError::new(
+                    s,
+                    bracket_start,
+                    bracket,
+                    "scan code alone in brackets",
+                ));
+            }
+            typed_modifiers_up(result, state);
+            match c {
+                '+' => result.push(KeyInput::ScanCodeDown(scancode)),
+                '-' => result.push(KeyInput::ScanCodeUp(scancode)),
+                _ => {
+                    for _ in 0..repeat {
+                        result.push(KeyInput::ScanCodeDown(scancode));
+                        if let Some(duration) = hold {
+                            result.push(KeyInput::Delay(duration));
+                        }
+                        result.push(KeyInput::ScanCodeUp(scancode));
+                    }
+                }
+            }
+            skip_close = c != '>';
+            part.clear();
+            in_bracket = false;
+            continue;
+        }
+
+        if c == '>' && key_mods_vec.is_empty() && hold.is_none() {
+            if let Some(keys) = aliases.get(&part) {
+                // like <ok> or <copy*2>, errors are put on the outermost alias
+                const RECURSION: &str = "alias not expanding to itself";
+                let error = |expected| ParseError::new(s, part_start, &part, expected);
+                if expanding.contains(&part) {
+                    return Err(error(RECURSION));
+                }
+                typed_modifiers_up(result, state);
+                expanding.push(part.clone());
+                for _ in 0..repeat {
+                    parse_tokens_with(keys, layout, aliases, expanding, state, result).map_err(
+                        |e| match e.expected.as_str() {
+                            RECURSION => error(RECURSION),
+                            _ => error("alias of a valid key string"),
+                        },
+                    )?;
+                }
+                expanding.pop();
+                part.clear();
+                in_bracket = false;
+                continue;
+            }
+
+            if let Some(text) = delay_duration(&part) {
+                // like <sleep:250> or <wait 1s>
+                let duration = parse_duration(text).ok_or_else(|| {
+                    let offset = part_start + part.len() - text.len();
+                    ParseError::new(s, offset, text, "duration like 250, 250ms or 1.5s")
+                })?;
+                typed_modifiers_up(result, state);
+                for _ in 0..repeat {
+                    result.push(KeyInput::Delay(duration));
+                }
+                part.clear();
+                in_bracket = false;
+                continue;
+            }
+
+            let unicode = parse_unicode_name(&part, layout)
+                .map_err(|_| ParseError::new(s, part_start, &part, "hex code point after 'u:'"))?;
+            if let Some(ch) = unicode {
+                // like <u:4e2d>
+                for _ in 0..repeat {
+                    push_unicode(ch, result, state);
+                }
+                part.clear();
+                in_bracket = false;
+                continue;
+            }
+        }
+
+        if !part.is_empty() {
+            let (key, modifiers) = parse_key_name(&part, layout)
+                .ok()
+                .or_else(|| alias::alias_key(&part, layout, aliases, expanding))
+                .ok_or_else(|| {
+                    ParseError::new(s, part_start, &part, "key name, char or virtual key code")
+                })?;
+            part.clear();
+            key_mods_vec.push((key, modifiers));
+        }
+
+        if c == '+' || c == '-' {
+            if seps.len() >= key_mods_vec.len() {
+                return Err(ParseError::new(
+                    s,
+                    i,
+                    &s[i..i + 1],
+                    "key name before '+' or '-'",
+                ));
+            }
+            seps.push(c == '+');
+            continue;
+        }
+
+        assert_eq!('>', c);
+        if key_mods_vec.is_empty() {
+            return Err(ParseError::new(s, i, ">", "key name after '<'"));
+        }
+
+        if seps.is_empty() {
+            // one name only, like <pagedown>
+            assert_eq!(1, key_mods_vec.len());
+            let (key, modifiers) = key_mods_vec[0];
+            if hold.is_some() {
+                // the char modifiers not held yet are pressed like a combination
+                let mut mod_keys: Vec<Key> = Vec::new();
+                for &(modifier, mod_key) in CHAR_MODIFIERS.iter() {
+                    if modifiers.contains(modifier) && state.modifier(modifier).is_none() {
+                        mod_keys.push(mod_key);
+                    }
+                }
+                push_combination(&mod_keys, key, repeat, hold, result, state);
+            } else {
+                for _ in 0..repeat {
+                    push_key_down_and_up(key, modifiers, result, state);
+                }
+            }
+            in_bracket = false;
+            continue;
+        }
+
+        if key_mods_vec.len() == seps.len() {
+            // end with '+' or '-'
+            for (&(key, _), &down) in key_mods_vec.iter().zip(&seps) {
+                if down {
+                    push_key_down(key, result, state);
+                } else {
+                    push_key_up(key, result, state);
+                }
+            }
+            in_bracket = false;
+            continue;
+        }
+
+        // key combination, like <ctrl+c>
+        assert_eq!(key_mods_vec.len(), seps.len() + 1);
+        if seps.iter().any(|&down| !down) {
+            return Err(ParseError::new(
+                s,
+                bracket_start,
+                &s[bracket_start..=i],
+                "'+' between keys of a combination",
+            ));
+        }
+
+        let keys: Vec<Key> = key_mods_vec.iter().map(|&(key, _)| key).collect();
+        let (&last, modifiers) = keys.split_last().unwrap();
+        push_combination(modifiers, last, repeat, hold, result, state);
+        in_bracket = false;
+    }
+    if in_bracket {
+        return Err(ParseError::new(s, s.len(), "", "'>'"));
+    }
+    Ok(())
+}
+
+#[allow(dead_code)]
+fn mod_keys_up(inputs: &mut Vec<KeyInput>, state: &mut KeyState) {
+    if let Some(key) = state.win.take() {
+        inputs.push(KeyInput::KeyUp(key));
+    }
+
+    if let Some(key) = state.shift.take() {
+        inputs.push(KeyInput::KeyUp(key));
+    }
+
+    if let Some(key) = state.ctrl.take() {
+        inputs.push(KeyInput::KeyUp(key));
+    }
+}
+
+// return key and modifiers to press, on the system layout when `layout` is
+// None
+pub(crate) fn parse_char(c: char, layout: Option<&Layout>) -> Result<(Key, Modifiers)> {
+    match layout {
+        Some(layout) => layout_char(c, layout),
+        None => system_char(c),
+    }
+}
+
+fn layout_char(c: char, layout: &Layout) -> Result<(Key, Modifiers)> {
+    match layout.lookup(c) {
+        Some(key_mods) => Ok(key_mods),
+        None => bail!("no key for char {:?} on layout {}", c, layout.name()),
+    }
+}
+
+#[cfg(windows)]
+fn system_char(c: char) -> Result<(Key, Modifiers)> {
+    let ret = unsafe { VkKeyScanW(c as _) };
+    if ret == -1 {
+        bail!(Error::with_chain(
+            std::io::Error::last_os_error(),
+            "VkKeyScan() failed"
+        ));
+    }
+    let vk: u8 = (ret & 0xFF) as _;
+    let mut modifiers = Modifiers::NONE;
+    if (ret & 0x0100) != 0 {
+        modifiers.insert(Modifiers::SHIFT);
+    }
+    // ctrl and alt together is AltGr
+    match ret & 0x0600 {
+        0x0600 => modifiers.insert(Modifiers::ALTGR),
+        0x0200 => modifiers.insert(Modifiers::CTRL),
+        0x0400 => modifiers.insert(Modifiers::ALT),
+        _ => {}
+    }
+    Ok((Key::from_vk(vk), modifiers))
+}
+
+#[cfg(not(windows))]
+fn system_char(c: char) -> Result<(Key, Modifiers)> {
+    layout_char(c, &US_LAYOUT)
+}
+
+fn dead_key_sequence(c: char, layout: Option<&Layout>) -> Option<[(Key, Modifiers); 2]> {
+    match layout {
+        Some(layout) => layout.dead_key_sequence(c),
+        None => system_dead_key_sequence(c),
+    }
+}
+
+#[cfg(windows)]
+fn system_dead_key_sequence(c: char) -> Option<[(Key, Modifiers); 2]> {
+    const MAPVK_VK_TO_CHAR: u32 = 2;
+    let (dead, base) = crate::layout::decompose(c)?;
+    let dead_key = system_char(dead.spacing()).ok()?;
+    // the high bit is set for dead keys
+    let mapped = unsafe { MapVirtualKeyW(dead_key.0.to_vk() as u32, MAPVK_VK_TO_CHAR) };
+    if mapped & 0x8000_0000 == 0 {
+        return None;
+    }
+    Some([dead_key, system_char(base).ok()?])
+}
+
+#[cfg(not(windows))]
+fn system_dead_key_sequence(c: char) -> Option<[(Key, Modifiers); 2]> {
+    US_LAYOUT.dead_key_sequence(c)
+}
+
+// type a char not on a key of its own, by a dead key then a base key when
+// the layout has them, like `^` then `e` for `ê`, as unicode otherwise
+fn push_dead_keys_or_unicode(
+    c: char,
+    layout: Option<&Layout>,
+    inputs: &mut Vec<KeyInput>,
+    state: &mut KeyState,
+) {
+    match dead_key_sequence(c, layout) {
+        Some(keys) => {
+            for (key, modifiers) in keys {
+                push_key_down_and_up(key, modifiers, inputs, state);
+            }
+        }
+        None => push_unicode(c, inputs, state),
+    }
+}
+
+// type a key with the modifiers of a char, modifiers held by the user are kept
+fn push_key_down_and_up(
+    key: Key,
+    modifiers: Modifiers,
+    inputs: &mut Vec<KeyInput>,
+    state: &mut KeyState,
+) {
+    for &(modifier, _) in CHAR_MODIFIERS.iter().rev() {
+        if state.typed.contains(modifier) && !modifiers.contains(modifier) {
+            if let Some(mod_key) = state.modifier(modifier).take() {
+                inputs.push(KeyInput::KeyUp(mod_key));
+            }
+            state.typed.remove(modifier);
+        }
+    }
+
+    for &(modifier, mod_key) in CHAR_MODIFIERS.iter() {
+        if modifiers.contains(modifier) && state.modifier(modifier).is_none() {
+            inputs.push(KeyInput::KeyDown(mod_key));
+            *state.modifier(modifier) = Some(mod_key);
+            state.typed.insert(modifier);
+        }
+    }
+
+    inputs.push(KeyInput::KeyDown(key));
+    inputs.push(KeyInput::KeyUp(key));
+}
+
+// most times a key or click can be repeated, more is more likely a typo than
+// a wish to wait for billions of inputs
+const MAX_REPEAT: u32 = 10_000;
+const REPEAT: &str = "repeat count from 1 to 10000";
+
+// a repeat count like the `5` of `tab*5`
+fn parse_repeat(count: &str) -> Option<u32> {
+    count.parse().ok().filter(|n| (1..=MAX_REPEAT).contains(n))
+}
+
+// index of the `sep` of a suffix, like the '*' of `tab*5`, not a `sep` char
+// alone like `<*>`
+fn suffix_start(part: &str, sep: char) -> Option<usize> {
+    match part.rfind(sep) {
+        Some(start) if start > 0 && start + 1 < part.len() => Some(start),
+        _ => None,
+    }
+}
+
+// the modifiers are held once, `key` is pressed `repeat` times and kept down
+// for `hold` each time
+fn push_combination(
+    modifiers: &[Key],
+    key: Key,
+    repeat: u32,
+    hold: Option<Duration>,
+    inputs: &mut Vec<KeyInput>,
+    state: &mut KeyState,
+) {
+    for &mod_key in modifiers.iter() {
+        push_key_down(mod_key, inputs, state);
+    }
+
+    for _ in 0..repeat {
+        push_key_down(key, inputs, state);
+        if let Some(duration) = hold {
+            inputs.push(KeyInput::Delay(duration));
+        }
+        push_key_up(key, inputs, state);
+    }
+
+    for &mod_key in modifiers.iter().rev() {
+        push_key_up(mod_key, inputs, state);
+    }
+}
+
+// what a char or key name types, in the other key syntaxes
+enum Unit {
+    Key(Key, Modifiers),
+    Unicode(char),
+}
+
+fn char_unit(c: char, layout: Option<&Layout>) -> Unit {
+    match parse_char(c, layout) {
+        Ok((key, modifiers)) => Unit::Key(key, modifiers),
+        // not on the layout
+        Err(_) => Unit::Unicode(c),
+    }
+}
+
+// the modifier keys of a char not already held by `prefix`, to press with it
+pub(crate) fn char_modifier_keys(modifiers: Modifiers, prefix: &[Key]) -> Vec<Key> {
+    let held = |modifier: Modifiers| {
+        prefix.iter().any(|key| match modifier {
+            Modifiers::SHIFT => key.is_shift(),
+            Modifiers::CTRL => key.is_ctrl(),
+            Modifiers::ALT => key.is_alt() && *key != Key::RAlt,
+            _ => *key == Key::RAlt,
+        })
+    };
+    CHAR_MODIFIERS
+        .iter()
+        .filter(|&&(modifier, _)| modifiers.contains(modifier) && !held(modifier))
+        .map(|&(_, key)| key)
+        .collect()
+}
+
+// `0x1e`, `1e` or `0xe048`, a set 1 scan code maybe extended
+fn parse_scancode(s: &str) -> Option<u16> {
+    let hex = s.strip_prefix("0x").unwrap_or(s);
+    let code = u16::from_str_radix(hex, 16).ok()?;
+    match code >> 8 {
+        0 | 0xE0 if code & 0xFF != 0 => Some(code),
+        _ => None,
+    }
+}
+
+// the duration text of `sleep:250`, `wait:250` or `wait 1s`
+fn delay_duration(part: &str) -> Option<&str> {
+    ["sleep:", "wait:", "wait "]
+        .iter()
+        .find_map(|prefix| part.strip_prefix(prefix))
+}
+
+// `250`, `250ms`, `1s` or `1.5s`, plain numbers are milliseconds
+fn parse_duration(s: &str) -> Option<Duration> {
+    let (number, unit_nanos) = if let Some(number) = s.strip_suffix("ms") {
+        (number, 1e6)
+    } else if let Some(number) = s.strip_suffix('s') {
+        (number, 1e9)
+    } else {
+        (s, 1e6)
+    };
+    if !number.starts_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+    let number: f64 = number.parse().ok()?;
+    let nanos = (number * unit_nanos).round();
+    if !nanos.is_finite() || nanos > u64::MAX as f64 {
+        return None;
+    }
+    Some(Duration::from_nanos(nanos as u64))
+}
+
+fn push_unicode(c: char, inputs: &mut Vec<KeyInput>, state: &mut KeyState) {
+    typed_modifiers_up(inputs, state);
+    inputs.push(KeyInput::Unicode(c));
+}
+
+// `u:` and a hex code point, or a single char not on the layout
+fn parse_unicode_name(s: &str, layout: Option<&Layout>) -> Result<Option<char>> {
+    if let Some(hex) = s.strip_prefix("u:") {
+        return match u32::from_str_radix(hex, 16).ok().and_then(char::from_u32) {
+            Some(c) => Ok(Some(c)),
+            None => bail!("invalid unicode code point {}", hex),
+        };
+    }
+    let mut chars = s.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if parse_char(c, layout).is_err() => Ok(Some(c)),
+        _ => Ok(None),
+    }
+}
+
+// release the modifiers pressed to type chars
+fn typed_modifiers_up(inputs: &mut Vec<KeyInput>, state: &mut KeyState) {
+    for &(modifier, _) in CHAR_MODIFIERS.iter().rev() {
+        if state.typed.contains(modifier) {
+            if let Some(mod_key) = state.modifier(modifier).take() {
+                inputs.push(KeyInput::KeyUp(mod_key));
+            }
+        }
+    }
+    state.typed = Modifiers::NONE;
+}
+
+// return key and modifiers to press
+fn parse_key_name(s: &str, layout: Option<&Layout>) -> Result<(Key, Modifiers)> {
+    if s.is_empty() {
+        return Err("expected key name".into());
+    }
+
+    if let Some(key) = Key::from_name(s) {
+        return Ok((key, Modifiers::NONE));
+    }
+
+    if let Ok(num) = s.parse::<u8>() {
+        return Ok((Key::from_vk(num), Modifiers::NONE));
+    }
+
+    if !ESCAPED_KEY_NAME_TO_CHAR.contains_key(s) && s.chars().count() != 1 {
+        return Err(format!("unknown key name {}", s).into());
+    }
+
+    let ch: char = if ESCAPED_KEY_NAME_TO_CHAR.contains_key(s) {
+        ESCAPED_KEY_NAME_TO_CHAR[s]
+    } else {
+        s.chars().next().unwrap()
+    };
+
+    parse_char(ch, layout)
+}
+
+fn push_key_down(key: Key, inputs: &mut Vec<KeyInput>, state: &mut KeyState) {
+    inputs.push(KeyInput::KeyDown(key));
+    if key.is_shift() {
+        state.shift = Some(key);
+    }
+    if key.is_ctrl() {
+        state.ctrl = Some(key);
+    }
+    if key.is_alt() {
+        state.alt = Some(key);
+    }
+    // right alt is AltGr on the layouts having chars that need AltGr
+    if key == Key::RAlt {
+        state.altgr = Some(key);
+    }
+    if key.is_win() {
+        state.win = Some(key);
+    }
+}
+
+fn push_key_up(key: Key, inputs: &mut Vec<KeyInput>, state: &mut KeyState) {
+    inputs.push(KeyInput::KeyUp(key));
+    if key.is_shift() {
+        state.shift = None;
+        state.typed.remove(Modifiers::SHIFT);
+    }
+    if key.is_ctrl() {
+        state.ctrl = None;
+        state.typed.remove(Modifiers::CTRL);
+    }
+    if key.is_alt() {
+        state.alt = None;
+        state.typed.remove(Modifiers::ALT);
+        if state.altgr == Some(key) {
+            state.altgr = None;
+            state.typed.remove(Modifiers::ALTGR);
+        }
+    }
+    if key.is_win() {
+        state.win = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::backend::RecordingBackend;
+
+    #[test]
+    fn type_abc() -> Result<()> {
+        println!("Testing start");
+        input("aBc")
+    }
+
+    #[test]
+    fn type_escaped() -> Result<()> {
+        println!("Testing start");
+        input("<lt>")
+    }
+
+    #[test]
+    fn show_desktop() -> Result<()> {
+        println!("Testing start");
+        input("<win+d>")
+    }
+
+    #[test]
+    fn switch_windows() -> Result<()> {
+        println!("Testing start");
+        input("<win+>23<win->")
+    }
+
+    #[test]
+    fn record_ctrl_c() -> Result<()> {
+        let rec = Arc::new(RecordingBackend::new());
+        backend::with_backend(rec.clone(), || input("<ctrl+c>"))?;
+        assert_eq!(
+            rec.take(),
+            vec![
+                Input::Key(KeyInput::KeyDown(Key::Ctrl)),
+                Input::Key(KeyInput::KeyDown(Key::C)),
+                Input::Key(KeyInput::KeyUp(Key::C)),
+                Input::Key(KeyInput::KeyUp(Key::Ctrl)),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn record_hold_and_release() -> Result<()> {
+        let rec = Arc::new(RecordingBackend::new());
+        backend::with_backend(rec.clone(), || input("<win+>2<win->"))?;
+        assert_eq!(
+            rec.take(),
+            vec![
+                Input::Key(KeyInput::KeyDown(Key::LWin)),
+                Input::Key(KeyInput::KeyDown(Key::Digit2)),
+                Input::Key(KeyInput::KeyUp(Key::Digit2)),
+                Input::Key(KeyInput::KeyUp(Key::LWin)),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn layout_chars() -> Result<()> {
+        let fr = Layout::builtin("fr")?;
+        assert_eq!(
+            parse_str_with("a1<é>", Some(&fr))?,
+            vec![
+                KeyInput::KeyDown(Key::Q),
+                KeyInput::KeyUp(Key::Q),
+                KeyInput::KeyDown(Key::Shift),
+                KeyInput::KeyDown(Key::Digit1),
+                KeyInput::KeyUp(Key::Digit1),
+                KeyInput::KeyUp(Key::Shift),
+                KeyInput::KeyDown(Key::Digit2),
+                KeyInput::KeyUp(Key::Digit2),
+            ]
+        );
+
+        let de = Layout::builtin("de")?;
+        assert_eq!(
+            parse_str_with("<ctrl+z>", Some(&de))?,
+            vec![
+                KeyInput::KeyDown(Key::Ctrl),
+                KeyInput::KeyDown(Key::Y),
+                KeyInput::KeyUp(Key::Y),
+                KeyInput::KeyUp(Key::Ctrl),
+            ]
+        );
+        assert_eq!(
+            parse_str_with("é", Some(&Layout::builtin("us")?))?,
+            vec![KeyInput::Unicode('é')]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn altgr_chars() -> Result<()> {
+        let de = Layout::builtin("de")?;
+        assert_eq!(
+            parse_str_with("@€{A", Some(&de))?,
+            vec![
+                KeyInput::KeyDown(Key::RAlt),
+                KeyInput::KeyDown(Key::Q),
+                KeyInput::KeyUp(Key::Q),
+                KeyInput::KeyDown(Key::E),
+                KeyInput::KeyUp(Key::E),
+                KeyInput::KeyDown(Key::Digit7),
+                KeyInput::KeyUp(Key::Digit7),
+                KeyInput::KeyUp(Key::RAlt),
+                KeyInput::KeyDown(Key::Shift),
+                KeyInput::KeyDown(Key::A),
+                KeyInput::KeyUp(Key::A),
+                KeyInput::KeyUp(Key::Shift),
+            ]
+        );
+        // a held right alt is AltGr
+        assert_eq!(
+            parse_str_with("<ralt+>@€<ralt->", Some(&de))?,
+            vec![
+                KeyInput::KeyDown(Key::RAlt),
+                KeyInput::KeyDown(Key::Q),
+                KeyInput::KeyUp(Key::Q),
+                KeyInput::KeyDown(Key::E),
+                KeyInput::KeyUp(Key::E),
+                KeyInput::KeyUp(Key::RAlt),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn held_modifiers_kept() -> Result<()> {
+        let us = Layout::builtin("us")?;
+        assert_eq!(
+            parse_str_with("<shift+>a<shift->B<ctrl+c>", Some(&us))?,
+            vec![
+                KeyInput::KeyDown(Key::Shift),
+                KeyInput::KeyDown(Key::A),
+                KeyInput::KeyUp(Key::A),
+                KeyInput::KeyUp(Key::Shift),
+                KeyInput::KeyDown(Key::Shift),
+                KeyInput::KeyDown(Key::B),
+                KeyInput::KeyUp(Key::B),
+                KeyInput::KeyUp(Key::Shift),
+                KeyInput::KeyDown(Key::Ctrl),
+                KeyInput::KeyDown(Key::C),
+                KeyInput::KeyUp(Key::C),
+                KeyInput::KeyUp(Key::Ctrl),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn virtual_key_codes() -> Result<()> {
+        let us = Layout::builtin("us")?;
+        let inputs = parse_str_with("<65><7><255>", Some(&us))?;
+        let downs: Vec<Key> = inputs
+            .iter()
+            .filter_map(|input| match input {
+                KeyInput::KeyDown(key) => Some(*key),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(vec![Key::A, Key::Vk07, Key::VkFF], downs);
+        Ok(())
+    }
+
+    #[test]
+    fn unicode_fallback() -> Result<()> {
+        let us = Layout::builtin("us")?;
+        assert_eq!(
+            parse_str_with("A中<u:6587><é>", Some(&us))?,
+            vec![
+                KeyInput::KeyDown(Key::Shift),
+                KeyInput::KeyDown(Key::A),
+                KeyInput::KeyUp(Key::A),
+                KeyInput::KeyUp(Key::Shift),
+                KeyInput::Unicode('中'),
+                KeyInput::Unicode('文'),
+                KeyInput::Unicode('é'),
+            ]
+        );
+        assert!(parse_str_with("<u:zz>", Some(&us)).is_err());
+        assert!(parse_str_with("<u:d800>", Some(&us)).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn dead_keys() -> Result<()> {
+        let de = Layout::builtin("de")?;
+        assert_eq!(
+            parse_str_with("ê`", Some(&de))?,
+            vec![
+                KeyInput::KeyDown(Key::Backquote),
+                KeyInput::KeyUp(Key::Backquote),
+                KeyInput::KeyDown(Key::E),
+                KeyInput::KeyUp(Key::E),
+                KeyInput::KeyDown(Key::Shift),
+                KeyInput::KeyDown(Key::Equal),
+                KeyInput::KeyUp(Key::Equal),
+                KeyInput::KeyUp(Key::Shift),
+                KeyInput::KeyDown(Key::Space),
+                KeyInput::KeyUp(Key::Space),
+            ]
+        );
+        let fr = Layout::builtin("fr")?;
+        assert_eq!(
+            parse_str_with("Ñ", Some(&fr))?,
+            vec![
+                KeyInput::KeyDown(Key::RAlt),
+                KeyInput::KeyDown(Key::Digit2),
+                KeyInput::KeyUp(Key::Digit2),
+                KeyInput::KeyUp(Key::RAlt),
+                KeyInput::KeyDown(Key::Shift),
+                KeyInput::KeyDown(Key::N),
+                KeyInput::KeyUp(Key::N),
+                KeyInput::KeyUp(Key::Shift),
+            ]
+        );
+        // no dead keys on the layout
+        let us = Layout::builtin("us")?;
+        assert_eq!(
+            parse_str_with("ê", Some(&us))?,
+            vec![KeyInput::Unicode('ê')]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn repeat_count() -> Result<()> {
+        let us = Layout::builtin("us")?;
+        let tab = [KeyInput::KeyDown(Key::Tab), KeyInput::KeyUp(Key::Tab)];
+        assert_eq!(parse_str_with("<tab*3>", Some(&us))?, tab.repeat(3));
+
+        let mut expected = vec![KeyInput::KeyDown(Key::Ctrl)];
+        for _ in 0..2 {
+            expected.push(KeyInput::KeyDown(Key::Right));
+            expected.push(KeyInput::KeyUp(Key::Right));
+        }
+        expected.push(KeyInput::KeyUp(Key::Ctrl));
+        assert_eq!(parse_str_with("<ctrl+right*2>", Some(&us))?, expected);
+
+        // typed modifiers stay down between repeats, '*' alone is the char
+        assert_eq!(
+            parse_str_with("<A*2><*>", Some(&us))?,
+            vec![
+                KeyInput::KeyDown(Key::Shift),
+                KeyInput::KeyDown(Key::A),
+                KeyInput::KeyUp(Key::A),
+                KeyInput::KeyDown(Key::A),
+                KeyInput::KeyUp(Key::A),
+                KeyInput::KeyUp(Key::Shift),
+                KeyInput::KeyDown(Key::Shift),
+                KeyInput::KeyDown(Key::Digit8),
+                KeyInput::KeyUp(Key::Digit8),
+                KeyInput::KeyUp(Key::Shift),
+            ]
+        );
+        assert_eq!(
+            parse_str_with("<u:4e2d*2>", Some(&us))?,
+            vec![KeyInput::Unicode('中'); 2]
+        );
+
+        let e = parse_str_with("<tab*0>", Some(&us)).unwrap_err();
+        assert_eq!((5, "0"), (e.offset, e.fragment.as_str()));
+        assert_eq!(10_000, parse_str_with("<a*10000>", Some(&us))?.len() / 2);
+        let e = parse_str_with("<a*4294967295>", Some(&us)).unwrap_err();
+        assert_eq!((3, REPEAT), (e.offset, e.expected.as_str()));
+        Ok(())
+    }
+
+    #[test]
+    fn delays() -> Result<()> {
+        let us = Layout::builtin("us")?;
+        assert_eq!(
+            parse_str_with("A<sleep:250>b<wait 1.5s><wait:2ms>", Some(&us))?,
+            vec![
+                KeyInput::KeyDown(Key::Shift),
+                KeyInput::KeyDown(Key::A),
+                KeyInput::KeyUp(Key::A),
+                KeyInput::KeyUp(Key::Shift),
+                KeyInput::Delay(Duration::from_millis(250)),
+                KeyInput::KeyDown(Key::B),
+                KeyInput::KeyUp(Key::B),
+                KeyInput::Delay(Duration::from_millis(1500)),
+                KeyInput::Delay(Duration::from_millis(2)),
+            ]
+        );
+        // still the sleep key
+        assert_eq!(
+            parse_str_with("<sleep>", Some(&us))?,
+            vec![KeyInput::KeyDown(Key::Sleep), KeyInput::KeyUp(Key::Sleep)]
+        );
+        let e = parse_str_with("<wait 1h>", Some(&us)).unwrap_err();
+        assert_eq!((6, "1h"), (e.offset, e.fragment.as_str()));
+        Ok(())
+    }
+
+    #[test]
+    fn holds() -> Result<()> {
+        let us = Layout::builtin("us")?;
+        let ms = Duration::from_millis;
+        assert_eq!(
+            parse_str_with("<space~500ms><shift+a~2s>", Some(&us))?,
+            vec![
+                KeyInput::KeyDown(Key::Space),
+                KeyInput::Delay(ms(500)),
+                KeyInput::KeyUp(Key::Space),
+                KeyInput::KeyDown(Key::Shift),
+                KeyInput::KeyDown(Key::A),
+                KeyInput::Delay(ms(2000)),
+                KeyInput::KeyUp(Key::A),
+                KeyInput::KeyUp(Key::Shift),
+            ]
+        );
+        // char modifiers are held too, but not again when already down
+        assert_eq!(
+            parse_str_with("<A~10><shift+><A~10*2><shift->", Some(&us))?,
+            vec![
+                KeyInput::KeyDown(Key::Shift),
+                KeyInput::KeyDown(Key::A),
+                KeyInput::Delay(ms(10)),
+                KeyInput::KeyUp(Key::A),
+                KeyInput::KeyUp(Key::Shift),
+                KeyInput::KeyDown(Key::Shift),
+                KeyInput::KeyDown(Key::A),
+                KeyInput::Delay(ms(10)),
+                KeyInput::KeyUp(Key::A),
+                KeyInput::KeyDown(Key::A),
+                KeyInput::Delay(ms(10)),
+                KeyInput::KeyUp(Key::A),
+                KeyInput::KeyUp(Key::Shift),
+            ]
+        );
+        assert_eq!(
+            parse_str_with("<~>", Some(&us))?,
+            vec![
+                KeyInput::KeyDown(Key::Shift),
+                KeyInput::KeyDown(Key::Backquote),
+                KeyInput::KeyUp(Key::Backquote),
+                KeyInput::KeyUp(Key::Shift),
+            ]
+        );
+        let e = parse_str_with("<a~soon>", Some(&us)).unwrap_err();
+        assert_eq!((3, "soon"), (e.offset, e.fragment.as_str()));
+        Ok(())
+    }
+
+    #[test]
+    fn parse_errors() {
+        let us = Layout::builtin("us").unwrap();
+        let err = |s: &str| parse_str_with(s, Some(&us)).unwrap_err();
+
+        let e = err("ab<ctlr+c>");
+        assert_eq!((3, "ctlr"), (e.offset, e.fragment.as_str()));
+        assert_eq!("key name, char or virtual key code", e.expected);
+        assert_eq!(
+            e.render(),
+            "expected key name, char or virtual key code, found \"ctlr\" at column 4\n\
+             ab<ctlr+c>\n   ^^^^"
+        );
+
+        let e = err("中<+a>");
+        assert_eq!((4, "+", 3), (e.offset, e.fragment.as_str(), e.column()));
+        assert_eq!((1, ">"), (err("<>").offset, err("<>").fragment.as_str()));
+        assert_eq!("<ctrl-c>", err("x<ctrl-c>").fragment);
+        assert_eq!("hex code point after 'u:'", err("<u:xyz>").expected);
+
+        let e = err("a<ctrl");
+        assert_eq!((6, ""), (e.offset, e.fragment.as_str()));
+        assert_eq!("expected '>', found end of input", e.to_string());
+        assert_eq!(
+            e.render(),
+            "expected '>', found end of input\na<ctrl\n      ^"
+        );
+    }
+
+    #[test]
+    fn aliases() -> Result<()> {
+        let us = Layout::builtin("us")?;
+        let aliases: alias::Aliases = HashMap::from([
+            ("ok".to_string(), "<enter>".to_string()),
+            ("copy".to_string(), "<ctrl+c>".to_string()),
+            ("paste_twice".to_string(), "<ctrl+v*2>".to_string()),
+            ("both".to_string(), "<copy>x<paste_twice>".to_string()),
+            ("loop".to_string(), "a<loop2>".to_string()),
+            ("loop2".to_string(), "<loop>".to_string()),
+        ]);
+        let parse = |s: &str| {
+            let mut result = Vec::new();
+            let mut state = KeyState::new();
+            parse_tokens_with(
+                s,
+                Some(&us),
+                &aliases,
+                &mut Vec::new(),
+                &mut state,
+                &mut result,
+            )
+            .map(|_| result)
+        };
+        let same = |s: &str, expected: &str| {
+            assert_eq!(
+                parse_str_with(expected, Some(&us)).unwrap(),
+                parse(s).unwrap()
+            );
+        };
+
+        same("<ok><shift+ok>", "<enter><shift+enter>");
+        same("A<copy*2>", "A<ctrl+c><ctrl+c>");
+        same("<both>", "<ctrl+c>x<ctrl+v*2>");
+
+        let e = parse("ab<loop*2>").unwrap_err();
+        assert_eq!((3, "loop"), (e.offset, e.fragment.as_str()));
+        assert_eq!("alias not expanding to itself", e.expected);
+
+        assert!(set_alias("hk_test_ok", "<enter>").is_ok());
+        assert!(set_alias("hk_test_loop", "<hk_test_loop>").is_err());
+        assert!(set_alias("hk_test_bad", "<enter").is_err());
+        assert!(set_alias("tab", "<enter>").is_err());
+        assert!(remove_alias("hk_test_ok"));
+        assert!(!remove_alias("hk_test_loop"));
+        Ok(())
+    }
+
+    #[test]
+    fn scancodes() -> Result<()> {
+        let us = Layout::builtin("us")?;
+        let parse = |s: &str| parse_str_with(s, Some(&us));
+        assert_eq!(
+            vec![
+                KeyInput::ScanCodeDown(0x1e),
+                KeyInput::ScanCodeUp(0x1e),
+                KeyInput::ScanCodeDown(0xe048),
+                KeyInput::Delay(Duration::from_millis(50)),
+                KeyInput::ScanCodeUp(0xe048),
+                KeyInput::ScanCodeDown(0xe048),
+                KeyInput::Delay(Duration::from_millis(50)),
+                KeyInput::ScanCodeUp(0xe048),
+                KeyInput::ScanCodeDown(0x2a),
+                KeyInput::KeyDown(Key::A),
+                KeyInput::KeyUp(Key::A),
+                KeyInput::ScanCodeUp(0x2a),
+            ],
+            parse("<sc:0x1e><sc:E048~50ms*2><sc:0x2a+>a<sc:0x2a->")?
+        );
+
+        let err = |s: &str| parse(s).unwrap_err();
+        let e = err("a<sc:0x1ff>");
+        assert_eq!((5, "0x1ff"), (e.offset, e.fragment.as_str()));
+        assert_eq!("scan code like 0x1e or 0xe048", e.expected);
+        assert_eq!("scan code like 0x1e or 0xe048", err("<sc:0xe000>").expected);
+        assert_eq!(
+            "scan code alone in brackets",
+            err("<ctrl+sc:0x1e>").expected
+        );
+
+        assert_eq!(
+            Some(KeyInput::ScanCodeDown(0xe04b)),
+            scancode_input(&KeyInput::KeyDown(Key::Left))
+        );
+        assert_eq!(None, scancode_input(&KeyInput::Unicode('中')));
+        Ok(())
+    }
+
+    #[test]
+    fn balanced() -> Result<()> {
+        let us = Layout::builtin("us")?;
+        let balanced = |s: &str, balance: Balance| {
+            let mut inputs = parse_str_with(s, Some(&us))?;
+            balance_keys(s, &mut inputs, balance, Some(&us)).map(|_| inputs)
+        };
+
+        let held = balanced("<ctrl+><win+>a<a+><sc:0x1e+>", Balance::Keep)?;
+        assert_eq!(
+            vec![
+                KeyInput::KeyUp(Key::Ctrl),
+                KeyInput::KeyUp(Key::LWin),
+                KeyInput::KeyUp(Key::A),
+                KeyInput::ScanCodeUp(0x1e),
+            ],
+            held_keys(&held)
+        );
+
+        let e = balanced("<ctrl+>ab<win+>", Balance::Error).unwrap_err();
+        assert_eq!("expected '<ctrl->', found end of input", e.to_string());
+        assert!(balanced("<ctrl+>ab<ctrl->A", Balance::Error).is_ok());
+
+        assert_eq!(
+            balanced("<ctrl+><win+>x", Balance::Release)?[4..],
+            [KeyInput::KeyUp(Key::LWin), KeyInput::KeyUp(Key::Ctrl)]
+        );
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(windows)]
+    fn hotkey() {
+        let id = hotkey_register("<ctrl+y>").unwrap();
+        println!("id {}", id);
+
+        let wait = hotkey_wait().unwrap();
+        println!("wait {}", wait);
+
+        hotkey_unregister(id);
+    }
+}
@@ -0,0 +1,271 @@
+use super::{
+    parse_repeat, parse_tokens, suffix_start, typed_modifiers_up, KeyInput, KeyState, ParseError,
+    REPEAT,
+};
+use crate::{
+    backend::Input,
+    layout::Layout,
+    mouse::{MouseButton, MouseInput, PressType},
+};
+
+// names of the mouse button tokens
+const BUTTONS: [(&str, MouseButton); 5] = [
+    ("click", MouseButton::ButtonLeft),
+    ("rclick", MouseButton::ButtonRight),
+    ("mclick", MouseButton::ButtonMiddle),
+    ("x1", MouseButton::ButtonX1),
+    ("x2", MouseButton::ButtonX2),
+];
+
+const POSITION: &str = "position like 300,400 or +10,-5";
+
+/// Whether `name` is a mouse token, like `click` or `wheel`
+pub(super) fn is_mouse_name(name: &str) -> bool {
+    BUTTONS.iter().any(|(button, _)| *button == name) || ["move", "wheel", "hwheel"].contains(&name)
+}
+
+fn button(name: &str) -> Option<MouseButton> {
+    BUTTONS
+        .iter()
+        .find(|(button, _)| *button == name)
+        .map(|&(_, button)| button)
+}
+
+// `300,400` to move to, or `+10,-5` to move by
+fn parse_position(
+    s: &str,
+    start: usize,
+    args: &str,
+) -> std::result::Result<MouseInput, ParseError> {
+    let err = || ParseError::new(s, start, args, POSITION);
+    let (x_text, y_text) = args.split_once(',').ok_or_else(err)?;
+    let (x, y) = match (x_text.parse::<i32>(), y_text.parse::<i32>()) {
+        (Ok(x), Ok(y)) => (x, y),
+        _ => return Err(err()),
+    };
+    let signed = |n: &str| n.starts_with('+') || n.starts_with('-');
+    // both signed or neither
+    match (signed(x_text), signed(y_text)) {
+        (true, true) => Ok(MouseInput::MoveDelta(x, y)),
+        (false, false) => Ok(MouseInput::MoveTo(x, y)),
+        _ => Err(err()),
+    }
+}
+
+// the inputs of a mouse token in brackets, like `rclick:300,400`, starting at
+// `start` of `s`, None when `content` is not a mouse token
+fn parse_mouse_token(
+    s: &str,
+    start: usize,
+    content: &str,
+) -> Option<std::result::Result<Vec<MouseInput>, ParseError>> {
+    // like <click+> or <x1->
+    if let Some((name, press)) = content
+        .strip_suffix('+')
+        .map(|name| (name, PressType::PressDown))
+        .or_else(|| {
+            content
+                .strip_suffix('-')
+                .map(|name| (name, PressType::PressUp))
+        })
+    {
+        if let Some(button) = button(name) {
+            return Some(Ok(vec![MouseInput::Press(button, press)]));
+        }
+    }
+
+    let (name, args) = match content.split_once(':') {
+        Some((name, args)) => (name, Some(args)),
+        None => (content, None),
+    };
+    let name = match suffix_start(name, '*') {
+        Some(star) if args.is_none() => &name[..star],
+        _ => name,
+    };
+    if !is_mouse_name(name) {
+        return None;
+    }
+    Some(parse_mouse_args(s, start, name, content))
+}
+
+fn parse_mouse_args(
+    s: &str,
+    start: usize,
+    name: &str,
+    content: &str,
+) -> std::result::Result<Vec<MouseInput>, ParseError> {
+    let mut rest = &content[name.len()..];
+    // like <click*2> or <click:300,400*2>
+    let mut repeat = 1;
+    if let Some(star) = rest.rfind('*').filter(|_| button(name).is_some()) {
+        let count = &rest[star + 1..];
+        repeat = parse_repeat(count)
+            .ok_or_else(|| ParseError::new(s, start + name.len() + star + 1, count, REPEAT))?;
+        rest = &rest[..star];
+    }
+    let args_start = start + name.len() + 1;
+    let args = rest.strip_prefix(':');
+
+    if let Some(button) = button(name) {
+        let mut inputs = Vec::new();
+        if let Some(args) = args {
+            inputs.push(parse_position(s, args_start, args)?);
+        }
+        for _ in 0..repeat {
+            inputs.push(MouseInput::Press(button, PressType::Click));
+        }
+        return Ok(inputs);
+    }
+
+    let args = match args {
+        Some(args) => args,
+        None => {
+            return Err(ParseError::new(
+                s,
+                start + name.len(),
+                rest,
+                "':' and arguments",
+            ))
+        }
+    };
+    match name {
+        "move" => Ok(vec![parse_position(s, args_start, args)?]),
+        _ => {
+            let amount = args.parse::<i32>().map_err(|_| {
+                ParseError::new(s, args_start, args, "wheel amount like 120 or -120")
+            })?;
+            match name {
+                "wheel" => Ok(vec![MouseInput::Wheel(amount)]),
+                _ => Ok(vec![MouseInput::HWheel(amount)]),
+            }
+        }
+    }
+}
+
+// parse the keys of `s[start..end]` from `state`, errors are reported in `s`
+fn push_keys(
+    s: &str,
+    start: usize,
+    end: usize,
+    layout: Option<&Layout>,
+    state: &mut KeyState,
+    inputs: &mut Vec<Input>,
+) -> std::result::Result<(), ParseError> {
+    let mut keys: Vec<KeyInput> = Vec::new();
+    parse_tokens(&s[start..end], layout, state, &mut keys)
+        .map_err(|e| ParseError::new(s, start + e.offset, &e.fragment, &e.expected))?;
+    inputs.extend(keys.into_iter().map(Input::Key));
+    Ok(())
+}
+
+// parse a key string with mouse tokens, with `layout`, None for the system
+// layout
+pub(super) fn parse_input_with(
+    s: &str,
+    layout: Option<&Layout>,
+) -> std::result::Result<Vec<Input>, ParseError> {
+    let mut inputs: Vec<Input> = Vec::new();
+    let mut state = KeyState::new();
+    // start of the keys before the next mouse token
+    let mut keys_start = 0;
+    let mut pos = 0;
+    while let Some(open) = s[pos..].find('<').map(|open| pos + open) {
+        let close = match s[open + 1..].find('>') {
+            Some(close) => open + 1 + close,
+            // reported by the key parser
+            None => break,
+        };
+        let mouse_inputs = match parse_mouse_token(s, open + 1, &s[open + 1..close]) {
+            Some(mouse_inputs) => mouse_inputs?,
+            None => {
+                pos = close + 1;
+                continue;
+            }
+        };
+        push_keys(s, keys_start, open, layout, &mut state, &mut inputs)?;
+        // chars typed with shift don't shift-click
+        let mut ups = Vec::new();
+        typed_modifiers_up(&mut ups, &mut state);
+        inputs.extend(ups.into_iter().map(Input::Key));
+        inputs.extend(mouse_inputs.into_iter().map(Input::Mouse));
+        keys_start = close + 1;
+        pos = close + 1;
+    }
+    push_keys(s, keys_start, s.len(), layout, &mut state, &mut inputs)?;
+    let mut ups = Vec::new();
+    typed_modifiers_up(&mut ups, &mut state);
+    inputs.extend(ups.into_iter().map(Input::Key));
+    Ok(inputs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::key::Key;
+
+    #[test]
+    fn mouse_and_keys() {
+        let us = Layout::builtin("us").unwrap();
+        let parse = |s: &str| parse_input_with(s, Some(&us)).unwrap();
+        let click = |button| Input::Mouse(MouseInput::Press(button, PressType::Click));
+        let key = |k: KeyInput| Input::Key(k);
+
+        assert_eq!(
+            parse("A<rclick:300,400>b"),
+            vec![
+                key(KeyInput::KeyDown(Key::Shift)),
+                key(KeyInput::KeyDown(Key::A)),
+                key(KeyInput::KeyUp(Key::A)),
+                key(KeyInput::KeyUp(Key::Shift)),
+                Input::Mouse(MouseInput::MoveTo(300, 400)),
+                click(MouseButton::ButtonRight),
+                key(KeyInput::KeyDown(Key::B)),
+                key(KeyInput::KeyUp(Key::B)),
+            ]
+        );
+        assert_eq!(
+            parse("<ctrl+><click><ctrl-><x1>"),
+            vec![
+                key(KeyInput::KeyDown(Key::Ctrl)),
+                click(MouseButton::ButtonLeft),
+                key(KeyInput::KeyUp(Key::Ctrl)),
+                click(MouseButton::ButtonX1),
+            ]
+        );
+        assert_eq!(
+            parse("<move:+10,-5><wheel:-120><hwheel:120><mclick*2><click+><click->"),
+            vec![
+                Input::Mouse(MouseInput::MoveDelta(10, -5)),
+                Input::Mouse(MouseInput::Wheel(-120)),
+                Input::Mouse(MouseInput::HWheel(120)),
+                click(MouseButton::ButtonMiddle),
+                click(MouseButton::ButtonMiddle),
+                Input::Mouse(MouseInput::Press(
+                    MouseButton::ButtonLeft,
+                    PressType::PressDown
+                )),
+                Input::Mouse(MouseInput::Press(
+                    MouseButton::ButtonLeft,
+                    PressType::PressUp
+                )),
+            ]
+        );
+    }
+
+    #[test]
+    fn mouse_errors() {
+        let us = Layout::builtin("us").unwrap();
+        let err = |s: &str| parse_input_with(s, Some(&us)).unwrap_err();
+
+        let e = err("ab<move:10,+5>");
+        assert_eq!((8, "10,+5"), (e.offset, e.fragment.as_str()));
+        assert_eq!(POSITION, e.expected);
+        assert_eq!(7, err("<wheel:up>").offset);
+        assert_eq!(REPEAT, err("<click*0>").expected);
+        assert_eq!(REPEAT, err("<click*10001>").expected);
+        assert_eq!("':' and arguments", err("<move>").expected);
+        // key errors after a mouse token
+        let e = err("<click>x<entr>");
+        assert_eq!((9, "entr"), (e.offset, e.fragment.as_str()));
+    }
+}
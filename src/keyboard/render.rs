@@ -0,0 +1,410 @@
+use std::time::Duration;
+
+use super::{parse_tokens, typed_modifiers_up, KeyInput, KeyState, CHAR_MODIFIERS, US_LAYOUT};
+use crate::{
+    key::Key,
+    layout::{Layout, Modifiers},
+};
+
+// modifiers of the layout levels, in preference order
+const LEVELS: [Modifiers; 4] = [
+    Modifiers::NONE,
+    Modifiers::SHIFT,
+    Modifiers::ALTGR,
+    Modifiers(Modifiers::SHIFT.0 | Modifiers::ALTGR.0),
+];
+
+// the longest `<a+b-...>` run of holds and releases tried
+const MAX_HOLD_RUN: usize = 8;
+
+/// Render key inputs as a key string, the shortest one parsing back to
+/// exactly `inputs` with the current layout.
+///
+/// Chars are used where possible (`Hello`, `<lt>`), then combinations
+/// (`<ctrl+c>`, `<tab*3>`), and holds and releases (`<ctrl+>`, `<ctrl->`)
+/// for keys left down or released alone.
+pub fn render(inputs: &[KeyInput]) -> String {
+    render_with(inputs, super::layout().as_deref())
+}
+
+struct Node {
+    state: KeyState,
+    cost: usize,
+    // position and node index of the previous node, and the token from it
+    back: Option<(usize, usize, String)>,
+}
+
+// every token is verified by parsing it from the parser state, so the
+// rendered string parses back to `inputs` whatever the token guesses are.
+// tokens consume at least one input, positions are visited in order and the
+// cheapest path, by chars, wins
+pub(super) fn render_with(inputs: &[KeyInput], layout: Option<&Layout>) -> String {
+    let proposal = layout.unwrap_or(&US_LAYOUT);
+    let mut nodes: Vec<Vec<Node>> = (0..=inputs.len()).map(|_| Vec::new()).collect();
+    nodes[0].push(Node {
+        state: KeyState::new(),
+        cost: 0,
+        back: None,
+    });
+    let mut end: Option<(usize, usize, usize)> = None;
+
+    for pos in 0..=inputs.len() {
+        for index in 0..nodes[pos].len() {
+            let state = nodes[pos][index].state.clone();
+            let cost = nodes[pos][index].cost;
+
+            // the typed modifiers released at the end of the string
+            let mut tail = Vec::new();
+            typed_modifiers_up(&mut tail, &mut state.clone());
+            if inputs[pos..] == tail[..] && end.map_or(true, |(_, _, c)| cost < c) {
+                end = Some((pos, index, cost));
+            }
+
+            for token in candidates(&inputs[pos..], &state, proposal) {
+                let mut next_state = state.clone();
+                let mut out = Vec::new();
+                if parse_tokens(&token, layout, &mut next_state, &mut out).is_err()
+                    || out.is_empty()
+                    || !inputs[pos..].starts_with(&out)
+                {
+                    continue;
+                }
+                let next = pos + out.len();
+                let next_cost = cost + token.chars().count();
+                let back = Some((pos, index, token));
+                match nodes[next].iter_mut().find(|n| n.state == next_state) {
+                    Some(node) if next_cost < node.cost => {
+                        node.cost = next_cost;
+                        node.back = back;
+                    }
+                    Some(_) => {}
+                    None => nodes[next].push(Node {
+                        state: next_state,
+                        cost: next_cost,
+                        back,
+                    }),
+                }
+            }
+        }
+    }
+
+    // holds and releases alone always reach the end
+    let (mut pos, mut index, _) = end.expect("no key string for inputs");
+    let mut tokens = Vec::new();
+    while let Some((prev_pos, prev_index, token)) = &nodes[pos][index].back {
+        tokens.push(token.as_str());
+        pos = *prev_pos;
+        index = *prev_index;
+    }
+    tokens.reverse();
+    tokens.concat()
+}
+
+fn candidates(rest: &[KeyInput], state: &KeyState, layout: &Layout) -> Vec<String> {
+    let mut tokens = Vec::new();
+
+    // a char, after the typed modifiers it releases and the ones it presses
+    let mod_key = |input: &KeyInput| match input {
+        KeyInput::KeyDown(key) | KeyInput::KeyUp(key) => {
+            CHAR_MODIFIERS.iter().any(|(_, mod_key)| mod_key == key)
+        }
+        _ => false,
+    };
+    if let Some(KeyInput::KeyDown(key)) = rest.iter().find(|input| !mod_key(input)) {
+        for modifiers in LEVELS {
+            match layout.char_of(*key, modifiers) {
+                Some('<') => tokens.push("<lt>".to_string()),
+                Some(c) => {
+                    tokens.push(c.to_string());
+                    // in brackets, to release the typed modifiers first
+                    if bracket_char(c) {
+                        tokens.push(format!("<{}>", c));
+                    }
+                }
+                None => {}
+            }
+        }
+        // a char typed by a dead key then another key, like `ê`
+        for (c, keys) in layout.dead_key_sequences() {
+            if keys[0].0 == *key {
+                tokens.push(c.to_string());
+            }
+        }
+    }
+
+    // bracket tokens release the typed modifiers first
+    let mut ups = Vec::new();
+    typed_modifiers_up(&mut ups, &mut state.clone());
+    let after = match rest.strip_prefix(&ups[..]) {
+        Some(after) => after,
+        None => return tokens,
+    };
+
+    match after.first() {
+        Some(KeyInput::Delay(duration)) => {
+            tokens.push(format!("<sleep:{}>", duration_text(*duration)))
+        }
+        Some(KeyInput::Unicode(c)) => {
+            if !c.is_control() && *c != '<' {
+                tokens.push(c.to_string());
+            }
+            tokens.push(format!("<u:{:x}>", *c as u32));
+        }
+        Some(KeyInput::ScanCodeDown(code)) => {
+            tokens.push(format!("<sc:{:#x}+>", code));
+            tokens.extend(scancode_stroke(after, *code));
+        }
+        Some(KeyInput::ScanCodeUp(code)) => tokens.push(format!("<sc:{:#x}->", code)),
+        _ => {}
+    }
+
+    if let Some(token) = combination(after, layout) {
+        tokens.push(token);
+    }
+
+    // holds and releases, like <ctrl+> or <a+b->
+    let mut run = String::new();
+    for input in after.iter().take(MAX_HOLD_RUN) {
+        let (key, sep) = match input {
+            KeyInput::KeyDown(key) => (key, '+'),
+            KeyInput::KeyUp(key) => (key, '-'),
+            _ => break,
+        };
+        run.push_str(&bracket_name(*key, layout));
+        run.push(sep);
+        tokens.push(format!("<{}>", run));
+    }
+    tokens
+}
+
+// a combination like <ctrl+c>, <tab*3> or <shift+a~2s>
+fn combination(inputs: &[KeyInput], layout: &Layout) -> Option<String> {
+    let downs: Vec<Key> = inputs
+        .iter()
+        .map_while(|input| match input {
+            KeyInput::KeyDown(key) => Some(*key),
+            _ => None,
+        })
+        .collect();
+    let (&last, _) = downs.split_last()?;
+
+    // the last key is pressed, maybe held, and released
+    let stroke = |at: usize| -> Option<(Option<Duration>, usize)> {
+        match inputs.get(at..)? {
+            [KeyInput::Delay(hold), KeyInput::KeyUp(key), ..] if *key == last => {
+                Some((Some(*hold), at + 2))
+            }
+            [KeyInput::KeyUp(key), ..] if *key == last => Some((None, at + 1)),
+            _ => None,
+        }
+    };
+    let (hold, mut at) = stroke(downs.len())?;
+    let mut repeat = 1;
+    while let Some(KeyInput::KeyDown(key)) = inputs.get(at) {
+        match stroke(at + 1) {
+            Some((next_hold, next_at)) if *key == last && next_hold == hold => {
+                repeat += 1;
+                at = next_at;
+            }
+            _ => break,
+        }
+    }
+
+    let names: Vec<String> = downs.iter().map(|key| bracket_name(*key, layout)).collect();
+    Some(format!("<{}{}>", names.join("+"), suffixes(hold, repeat)))
+}
+
+// a scan code pressed and released, like <sc:0x1e> or <sc:0xe048~50ms*2>
+fn scancode_stroke(inputs: &[KeyInput], code: u16) -> Option<String> {
+    let stroke = |at: usize| -> Option<(Option<Duration>, usize)> {
+        let (down, up) = (KeyInput::ScanCodeDown(code), KeyInput::ScanCodeUp(code));
+        match inputs.get(at..)? {
+            [d, KeyInput::Delay(hold), u, ..] if *d == down && *u == up => {
+                Some((Some(*hold), at + 3))
+            }
+            [d, u, ..] if *d == down && *u == up => Some((None, at + 2)),
+            _ => None,
+        }
+    };
+    let (hold, mut at) = stroke(0)?;
+    let mut repeat = 1;
+    while let Some((next_hold, next_at)) = stroke(at) {
+        if next_hold != hold {
+            break;
+        }
+        repeat += 1;
+        at = next_at;
+    }
+    Some(format!("<sc:{:#x}{}>", code, suffixes(hold, repeat)))
+}
+
+// the hold and repeat suffixes of a bracket, like `~2s*3`
+fn suffixes(hold: Option<Duration>, repeat: u32) -> String {
+    let mut text = String::new();
+    if let Some(hold) = hold {
+        text.push_str(&format!("~{}", duration_text(hold)));
+    }
+    if repeat > 1 {
+        text.push_str(&format!("*{}", repeat));
+    }
+    text
+}
+
+// a name parsing back to `key` inside brackets
+pub(super) fn bracket_name(key: Key, layout: &Layout) -> String {
+    if let Some(name) = key.name() {
+        return name.to_string();
+    }
+    match layout.char_of(key, Modifiers::NONE) {
+        Some('<') => "lt".to_string(),
+        Some('>') => "gt".to_string(),
+        Some('-') => "minus".to_string(),
+        Some('+') => "plus".to_string(),
+        Some(c) if bracket_char(c) => c.to_string(),
+        _ => key.to_vk().to_string(),
+    }
+}
+
+// chars standing for themselves in brackets, digits are virtual key codes
+fn bracket_char(c: char) -> bool {
+    !c.is_ascii_digit() && !c.is_control() && !c.is_whitespace() && !"<>+-".contains(c)
+}
+
+// like 2s, 250ms or 1.5ms
+fn duration_text(duration: Duration) -> String {
+    let nanos = duration.as_nanos();
+    if nanos % 1_000_000_000 == 0 {
+        format!("{}s", nanos / 1_000_000_000)
+    } else if nanos % 1_000_000 == 0 {
+        format!("{}ms", nanos / 1_000_000)
+    } else {
+        let fraction = format!("{:06}", nanos % 1_000_000);
+        format!("{}.{}ms", nanos / 1_000_000, fraction.trim_end_matches('0'))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keyboard::parse_str_with;
+    use crate::rng::Rng;
+
+    fn round_trip(s: &str, layout: &Layout) -> String {
+        render_with(&parse_str_with(s, Some(layout)).unwrap(), Some(layout))
+    }
+
+    #[test]
+    fn canonical() {
+        let us = Layout::builtin("us").unwrap();
+        for s in [
+            "<ctrl+c>Hello, World!<lt>>",
+            "<tab*3><ctrl+right*2>",
+            "<ctrl+>abc",
+            "<win+>23<win->",
+            "中文<u:a>",
+            "<sleep:250ms><space~1500ms><shift+a~2s*2>",
+            "<ctrl+shift+esc><alt+f4>",
+            "<f5><enter>",
+            "<sc:0x1e><sc:0xe048~50ms*2><sc:0x2a+>a<sc:0x2a->",
+        ] {
+            assert_eq!(s, round_trip(s, &us));
+        }
+        assert_eq!("<tab*3>", round_trip("<tab><tab><tab>", &us));
+        // the second shift is pressed again
+        assert_eq!("A<B>c", round_trip("<shift+a><shift+b>c", &us));
+        assert_eq!("<sleep:1s>", round_trip("<wait 1000>", &us));
+
+        let de = Layout::builtin("de").unwrap();
+        assert_eq!("@€{z<ctrl+z>", round_trip("@€{z<ctrl+z>", &de));
+        assert_eq!("ê^`ñ", round_trip("ê^`ñ", &de));
+    }
+
+    #[test]
+    fn unbalanced() {
+        let us = Layout::builtin("us").unwrap();
+        let inputs = [
+            KeyInput::KeyUp(Key::Shift),
+            KeyInput::KeyDown(Key::LCtrl),
+            KeyInput::KeyDown(Key::Digit1),
+        ];
+        assert_eq!("<shift-lctrl+49+>", render_with(&inputs, Some(&us)));
+    }
+
+    // below `n`, deterministic for a seeded `rng`
+    fn below(rng: &mut Rng, n: usize) -> usize {
+        (rng.next_u64() % n as u64) as usize
+    }
+
+    fn random_inputs(rng: &mut Rng) -> Vec<KeyInput> {
+        const KEYS: [Key; 12] = [
+            Key::A,
+            Key::Digit1,
+            Key::Comma,
+            Key::Space,
+            Key::Tab,
+            Key::Shift,
+            Key::Ctrl,
+            Key::RAlt,
+            Key::LWin,
+            Key::Q,
+            Key::IntlBackslash,
+            Key::F5,
+        ];
+        let mut inputs = Vec::new();
+        for _ in 0..below(rng, 12) {
+            let key = KEYS[below(rng, KEYS.len())];
+            match below(rng, 9) {
+                0 => inputs.push(KeyInput::KeyDown(key)),
+                1 => inputs.push(KeyInput::KeyUp(key)),
+                2 => inputs.push(KeyInput::Unicode(['中', 'é', '<', '\n'][below(rng, 4)])),
+                3 => inputs.push(KeyInput::Delay(Duration::from_micros(
+                    [1, 1500, 250_000, 2_000_000][below(rng, 4)],
+                ))),
+                4 => {
+                    let code = [0x1E, 0xE048][below(rng, 2)];
+                    match below(rng, 3) {
+                        0 => inputs.push(KeyInput::ScanCodeDown(code)),
+                        1 => inputs.push(KeyInput::ScanCodeUp(code)),
+                        _ => {
+                            inputs.push(KeyInput::ScanCodeDown(code));
+                            inputs.push(KeyInput::ScanCodeUp(code));
+                        }
+                    }
+                }
+                5 => {
+                    // a char with modifiers
+                    let modifier = KEYS[5 + below(rng, 3)];
+                    inputs.push(KeyInput::KeyDown(modifier));
+                    inputs.push(KeyInput::KeyDown(key));
+                    inputs.push(KeyInput::KeyUp(key));
+                    inputs.push(KeyInput::KeyUp(modifier));
+                }
+                _ => {
+                    inputs.push(KeyInput::KeyDown(key));
+                    inputs.push(KeyInput::KeyUp(key));
+                }
+            }
+        }
+        inputs
+    }
+
+    #[test]
+    fn parse_render_round_trip() {
+        let mut rng = Rng::new(0x2545F4914F6CDD1D);
+        for name in ["us", "de", "fr"] {
+            let layout = Layout::builtin(name).unwrap();
+            for _ in 0..300 {
+                let inputs = random_inputs(&mut rng);
+                let s = render_with(&inputs, Some(&layout));
+                assert_eq!(
+                    parse_str_with(&s, Some(&layout)).ok(),
+                    Some(inputs.clone()),
+                    "{} renders {:?} as {:?}",
+                    name,
+                    inputs,
+                    s
+                );
+            }
+        }
+    }
+}
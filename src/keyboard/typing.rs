@@ -0,0 +1,151 @@
+use std::time::Duration;
+
+use super::KeyInput;
+use crate::{
+    backend::Input,
+    mouse::{MouseInput, PressType},
+    rng::Rng,
+};
+
+/// How fast `input_with` types, the default sends everything at once
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TypingOptions {
+    /// wait after a key or button is released, a char typed or the mouse moved
+    pub delay: Duration,
+    /// wait after a key or button is pressed down
+    pub hold: Duration,
+    /// at most this much is randomly added to or taken from every wait
+    pub jitter: Duration,
+    /// seed of the jitter, None for a new one each time
+    pub seed: Option<u64>,
+}
+
+impl TypingOptions {
+    /// Like a typist at `wpm` words per minute, 5 chars a word, keys are held
+    /// a third of the time of a char, with a quarter of it as jitter
+    pub fn from_wpm(wpm: u32) -> TypingOptions {
+        let per_char = Duration::from_secs(12) / wpm.max(1);
+        TypingOptions {
+            delay: per_char - per_char / 3,
+            hold: per_char / 3,
+            jitter: per_char / 4,
+            seed: None,
+        }
+    }
+}
+
+// the wait after `input`, None after a delay of the key string
+fn wait_after(input: &Input, options: &TypingOptions) -> Option<Duration> {
+    match input {
+        Input::Key(KeyInput::Delay(_)) => None,
+        Input::Key(KeyInput::KeyDown(_) | KeyInput::ScanCodeDown(_))
+        | Input::Mouse(MouseInput::Press(_, PressType::PressDown)) => Some(options.hold),
+        _ => Some(options.delay),
+    }
+}
+
+fn jittered(wait: Duration, jitter: Duration, rng: &mut Rng) -> Duration {
+    let offset = jitter.as_secs_f64() * rng.next_signed();
+    Duration::from_secs_f64((wait.as_secs_f64() + offset).max(0.0))
+}
+
+/// The inputs with the waits of `options` between them, as delays. Delays
+/// already in `inputs` are kept and nothing is added next to them.
+pub fn typing_schedule(inputs: &[Input], options: &TypingOptions) -> Vec<Input> {
+    let mut rng = match options.seed {
+        Some(seed) => Rng::new(seed),
+        None => Rng::from_time(),
+    };
+    let mut scheduled = Vec::new();
+    for (i, input) in inputs.iter().enumerate() {
+        scheduled.push(*input);
+        let next = match inputs.get(i + 1) {
+            Some(next) => next,
+            None => break,
+        };
+        if let Input::Key(KeyInput::Delay(_)) = next {
+            continue;
+        }
+        if let Some(wait) = wait_after(input, options) {
+            let wait = jittered(wait, options.jitter, &mut rng);
+            if !wait.is_zero() {
+                scheduled.push(Input::Key(KeyInput::Delay(wait)));
+            }
+        }
+    }
+    scheduled
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::key::Key;
+
+    fn delays(inputs: &[Input]) -> Vec<Duration> {
+        inputs
+            .iter()
+            .filter_map(|input| match input {
+                Input::Key(KeyInput::Delay(d)) => Some(*d),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn waits() {
+        let ms = Duration::from_millis;
+        let inputs = [
+            Input::Key(KeyInput::KeyDown(Key::A)),
+            Input::Key(KeyInput::KeyUp(Key::A)),
+            Input::Key(KeyInput::Unicode('中')),
+            Input::Key(KeyInput::Delay(ms(500))),
+            Input::Key(KeyInput::KeyDown(Key::B)),
+            Input::Key(KeyInput::KeyUp(Key::B)),
+        ];
+        let options = TypingOptions {
+            delay: ms(50),
+            hold: ms(20),
+            ..TypingOptions::default()
+        };
+        assert_eq!(
+            typing_schedule(&inputs, &options),
+            vec![
+                inputs[0],
+                Input::Key(KeyInput::Delay(ms(20))),
+                inputs[1],
+                Input::Key(KeyInput::Delay(ms(50))),
+                inputs[2],
+                inputs[3],
+                inputs[4],
+                Input::Key(KeyInput::Delay(ms(20))),
+                inputs[5],
+            ]
+        );
+        assert_eq!(
+            typing_schedule(&inputs, &TypingOptions::default()),
+            inputs.to_vec()
+        );
+    }
+
+    #[test]
+    fn jitter() {
+        let inputs = [
+            Input::Key(KeyInput::KeyDown(Key::A)),
+            Input::Key(KeyInput::KeyUp(Key::A)),
+        ]
+        .repeat(50);
+        let options = TypingOptions {
+            seed: Some(7),
+            ..TypingOptions::from_wpm(60)
+        };
+        assert_eq!(Duration::from_millis(200), options.delay + options.hold);
+
+        let waits = delays(&typing_schedule(&inputs, &options));
+        assert_eq!(99, waits.len());
+        let (min, max) = (waits.iter().min().unwrap(), waits.iter().max().unwrap());
+        assert!(*min >= options.hold - options.jitter);
+        assert!(*max <= options.delay + options.jitter);
+        assert!(waits.iter().any(|w| *w != waits[0]));
+        assert_eq!(waits, delays(&typing_schedule(&inputs, &options)));
+    }
+}
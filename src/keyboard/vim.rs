@@ -0,0 +1,281 @@
+use std::sync::RwLock;
+
+use super::{
+    balance_keys, char_modifier_keys, char_unit, layout, push_combination,
+    push_dead_keys_or_unicode, push_key_down_and_up, typed_modifiers_up, KeyInput, KeyState,
+    ParseError, Unit, BALANCE,
+};
+use crate::{
+    key::Key,
+    layout::{Layout, Modifiers},
+};
+
+/// Keys typed for `<Leader>` and `<LocalLeader>`, in vim notation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VimLeaders {
+    pub leader: String,
+    pub local_leader: String,
+}
+
+impl Default for VimLeaders {
+    /// a backslash for both, like vim
+    fn default() -> VimLeaders {
+        VimLeaders {
+            leader: "\\".to_string(),
+            local_leader: "\\".to_string(),
+        }
+    }
+}
+
+lazy_static! {
+    static ref VIM_LEADERS: RwLock<VimLeaders> = RwLock::new(VimLeaders::default());
+}
+
+/// Set the keys of `<Leader>` and `<LocalLeader>`, leaders in them are typed
+/// as text
+pub fn set_vim_leaders(leaders: VimLeaders) {
+    *VIM_LEADERS.write().unwrap() = leaders;
+}
+
+// vim key names, lowercase
+const VIM_KEYS: [(&str, Key); 39] = [
+    ("bs", Key::Backspace),
+    ("tab", Key::Tab),
+    ("nl", Key::Enter),
+    ("cr", Key::Enter),
+    ("return", Key::Enter),
+    ("enter", Key::Enter),
+    ("esc", Key::Esc),
+    ("space", Key::Space),
+    ("del", Key::Delete),
+    ("up", Key::Up),
+    ("down", Key::Down),
+    ("left", Key::Left),
+    ("right", Key::Right),
+    ("help", Key::Help),
+    ("insert", Key::Insert),
+    ("home", Key::Home),
+    ("end", Key::End),
+    ("pageup", Key::PageUp),
+    ("pagedown", Key::PageDown),
+    ("khome", Key::Home),
+    ("kend", Key::End),
+    ("kpageup", Key::PageUp),
+    ("kpagedown", Key::PageDown),
+    ("kplus", Key::Add),
+    ("kminus", Key::Subtract),
+    ("kmultiply", Key::Multiply),
+    ("kdivide", Key::Divide),
+    ("kpoint", Key::Decimal),
+    ("kenter", Key::Enter),
+    ("k0", Key::Numpad0),
+    ("k1", Key::Numpad1),
+    ("k2", Key::Numpad2),
+    ("k3", Key::Numpad3),
+    ("k4", Key::Numpad4),
+    ("k5", Key::Numpad5),
+    ("k6", Key::Numpad6),
+    ("k7", Key::Numpad7),
+    ("k8", Key::Numpad8),
+    ("k9", Key::Numpad9),
+];
+
+// vim names of chars, lowercase
+const VIM_CHARS: [(&str, char); 3] = [("lt", '<'), ("bslash", '\\'), ("bar", '|')];
+
+// the key of a `S-`, `C-`, `M-`, `A-`, `D-` or `T-` modifier
+fn modifier_key(c: char) -> Option<Key> {
+    match c.to_ascii_uppercase() {
+        'S' => Some(Key::Shift),
+        'C' => Some(Key::Ctrl),
+        'M' | 'A' | 'T' => Some(Key::Alt),
+        'D' => Some(Key::LWin),
+        _ => None,
+    }
+}
+
+// the unit of a name in brackets without modifiers, like `CR` or `x`
+fn unit_of(name: &str, layout: Option<&Layout>) -> Option<Unit> {
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Some(char_unit(c, layout));
+    }
+    let lower = name.to_ascii_lowercase();
+    if let Some(&(_, key)) = VIM_KEYS.iter().find(|(vim_name, _)| *vim_name == lower) {
+        return Some(Unit::Key(key, Modifiers::NONE));
+    }
+    if let Some(&(_, c)) = VIM_CHARS.iter().find(|(vim_name, _)| *vim_name == lower) {
+        return Some(char_unit(c, layout));
+    }
+    // function keys, f1 to f24
+    match lower.strip_prefix('f') {
+        Some(n) if n.parse::<u8>().is_ok() => {
+            Key::from_name(&lower).map(|key| Unit::Key(key, Modifiers::NONE))
+        }
+        _ => None,
+    }
+}
+
+// push the keys of `<...>`, false when not vim notation, then typed as text
+fn push_bracket(
+    name: &str,
+    layout: Option<&Layout>,
+    leaders: Option<&VimLeaders>,
+    inputs: &mut Vec<KeyInput>,
+    state: &mut KeyState,
+) -> bool {
+    let mut mod_keys: Vec<Key> = Vec::new();
+    let mut name = name;
+    // like C-S- of <C-S-x>, the last char is a name, like <C-->
+    while name.len() > 2 && name.as_bytes()[1] == b'-' {
+        match modifier_key(name.as_bytes()[0] as char) {
+            Some(key) if !mod_keys.contains(&key) => mod_keys.push(key),
+            _ => return false,
+        }
+        name = &name[2..];
+    }
+
+    if let (true, Some(leaders)) = (mod_keys.is_empty(), leaders) {
+        let leader = match name.to_ascii_lowercase().as_str() {
+            "leader" => Some(&leaders.leader),
+            "localleader" => Some(&leaders.local_leader),
+            _ => None,
+        };
+        if let Some(leader) = leader {
+            typed_modifiers_up(inputs, state);
+            parse_tokens(leader, layout, None, inputs, state);
+            return true;
+        }
+    }
+
+    let unit = match unit_of(name, layout) {
+        Some(unit) => unit,
+        None => return false,
+    };
+    let (key, char_modifiers) = match unit {
+        Unit::Key(key, char_modifiers) => (key, char_modifiers),
+        Unit::Unicode(c) => {
+            push_dead_keys_or_unicode(c, layout, inputs, state);
+            return true;
+        }
+    };
+    if mod_keys.is_empty() {
+        push_key_down_and_up(key, char_modifiers, inputs, state);
+        return true;
+    }
+
+    typed_modifiers_up(inputs, state);
+    // <C-W> is <C-w> in vim, not <C-S-w>
+    let ctrl_letter = mod_keys.contains(&Key::Ctrl) && name.len() == 1;
+    if !ctrl_letter || !name.as_bytes()[0].is_ascii_uppercase() {
+        let extra = char_modifier_keys(char_modifiers, &mod_keys);
+        mod_keys.extend(extra);
+    }
+    push_combination(&mod_keys, key, 1, None, inputs, state);
+    true
+}
+
+// parse `s` from `state`, leaders are typed as text when None
+fn parse_tokens(
+    s: &str,
+    layout: Option<&Layout>,
+    leaders: Option<&VimLeaders>,
+    inputs: &mut Vec<KeyInput>,
+    state: &mut KeyState,
+) {
+    let mut pos = 0;
+    while let Some(c) = s[pos..].chars().next() {
+        if c == '<' {
+            if let Some(end) = s[pos + 1..].find('>') {
+                let name = &s[pos + 1..pos + 1 + end];
+                if push_bracket(name, layout, leaders, inputs, state) {
+                    pos += end + 2;
+                    continue;
+                }
+            }
+        }
+        match char_unit(c, layout) {
+            Unit::Key(key, modifiers) => push_key_down_and_up(key, modifiers, inputs, state),
+            Unit::Unicode(c) => push_dead_keys_or_unicode(c, layout, inputs, state),
+        }
+        pos += c.len_utf8();
+    }
+}
+
+/// Parse vim key notation, like `<C-w>v<Leader>f<CR>`, into the key inputs
+/// of the matching key string. Brackets which aren't vim notation are text,
+/// like in vim.
+pub fn parse_vim(s: &str) -> std::result::Result<Vec<KeyInput>, ParseError> {
+    let layout = layout();
+    let leaders = VIM_LEADERS.read().unwrap().clone();
+    let mut inputs = parse_vim_with(s, layout.as_deref(), Some(&leaders));
+    let balance = *BALANCE.read().unwrap();
+    balance_keys(s, &mut inputs, balance, layout.as_deref())?;
+    Ok(inputs)
+}
+
+// parse with `layout`, None for the system layout
+pub(super) fn parse_vim_with(
+    s: &str,
+    layout: Option<&Layout>,
+    leaders: Option<&VimLeaders>,
+) -> Vec<KeyInput> {
+    let mut result: Vec<KeyInput> = Vec::new();
+    let mut state = KeyState::new();
+    parse_tokens(s, layout, leaders, &mut result, &mut state);
+    typed_modifiers_up(&mut result, &mut state);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keyboard::parse_str_with;
+
+    #[test]
+    fn same_as_key_strings() {
+        let us = Layout::builtin("us").unwrap();
+        let leaders = VimLeaders {
+            leader: "<Space>".to_string(),
+            local_leader: ",".to_string(),
+        };
+        for (vim, native) in [
+            ("<C-w>v", "<ctrl+w>v"),
+            ("<C-W>", "<ctrl+w>"),
+            ("<C-S-w>", "<ctrl+shift+w>"),
+            ("<M-x>", "<alt+x>"),
+            ("<M-X>", "<alt+shift+x>"),
+            ("<S-Tab>", "<shift+tab>"),
+            (":wq<CR><Esc>", ":wq<enter><esc>"),
+            ("<Leader>ff<LocalLeader>g", "<space>ff,g"),
+            ("<lt>Bar> <Bar><bslash>", "<lt>Bar> |\\"),
+            (
+                "<C-->a<x-y><F12><k5><D-a>",
+                "<ctrl+minus>a<lt>x-y><f12><numpad5><win+a>",
+            ),
+        ] {
+            assert_eq!(
+                parse_str_with(native, Some(&us)).unwrap(),
+                parse_vim_with(vim, Some(&us), Some(&leaders)),
+                "{} and {}",
+                vim,
+                native
+            );
+        }
+    }
+
+    #[test]
+    fn default_leader() {
+        let us = Layout::builtin("us").unwrap();
+        let leaders = VimLeaders::default();
+        assert_eq!(
+            parse_str_with("\\w", Some(&us)).unwrap(),
+            parse_vim_with("<leader>w", Some(&us), Some(&leaders))
+        );
+        // not expanded inside leaders
+        assert_eq!(
+            parse_str_with("<lt>Leader>", Some(&us)).unwrap(),
+            parse_vim_with("<Leader>", Some(&us), None)
+        );
+    }
+}
@@ -0,0 +1,278 @@
+use super::{
+    balance_keys, char_modifier_keys, char_unit, layout, push_combination,
+    push_dead_keys_or_unicode, push_key_down_and_up, typed_modifiers_up, KeyInput, KeyState,
+    ParseError, Unit, BALANCE,
+};
+use crate::{
+    key::Key,
+    layout::{Layout, Modifiers},
+};
+
+// X keysym names and values, latin-1 keysyms are their chars
+const KEYSYMS: [(&str, u32); 108] = [
+    ("BackSpace", 0xFF08),
+    ("Tab", 0xFF09),
+    ("Clear", 0xFF0B),
+    ("Return", 0xFF0D),
+    ("Pause", 0xFF13),
+    ("Scroll_Lock", 0xFF14),
+    ("Escape", 0xFF1B),
+    ("Muhenkan", 0xFF22),
+    ("Henkan", 0xFF23),
+    ("Hiragana_Katakana", 0xFF27),
+    ("Hangul_Hanja", 0xFF34),
+    ("Hangul_Jeonja", 0xFF38),
+    ("Home", 0xFF50),
+    ("Left", 0xFF51),
+    ("Up", 0xFF52),
+    ("Right", 0xFF53),
+    ("Down", 0xFF54),
+    ("Prior", 0xFF55),
+    ("Page_Up", 0xFF55),
+    ("Next", 0xFF56),
+    ("Page_Down", 0xFF56),
+    ("End", 0xFF57),
+    ("Select", 0xFF60),
+    ("Print", 0xFF61),
+    ("Execute", 0xFF62),
+    ("Insert", 0xFF63),
+    ("Menu", 0xFF67),
+    ("Help", 0xFF6A),
+    ("Mode_switch", 0xFF7E),
+    ("Num_Lock", 0xFF7F),
+    ("KP_Multiply", 0xFFAA),
+    ("KP_Add", 0xFFAB),
+    ("KP_Separator", 0xFFAC),
+    ("KP_Subtract", 0xFFAD),
+    ("KP_Decimal", 0xFFAE),
+    ("KP_Divide", 0xFFAF),
+    ("KP_0", 0xFFB0),
+    ("KP_1", 0xFFB1),
+    ("KP_2", 0xFFB2),
+    ("KP_3", 0xFFB3),
+    ("KP_4", 0xFFB4),
+    ("KP_5", 0xFFB5),
+    ("KP_6", 0xFFB6),
+    ("KP_7", 0xFFB7),
+    ("KP_8", 0xFFB8),
+    ("KP_9", 0xFFB9),
+    ("Shift_L", 0xFFE1),
+    ("Shift_R", 0xFFE2),
+    ("Control_L", 0xFFE3),
+    ("Control_R", 0xFFE4),
+    ("Caps_Lock", 0xFFE5),
+    ("Alt_L", 0xFFE9),
+    ("Alt_R", 0xFFEA),
+    ("Super_L", 0xFFEB),
+    ("Super_R", 0xFFEC),
+    ("Delete", 0xFFFF),
+    ("XF86AudioLowerVolume", 0x1008FF11),
+    ("XF86AudioMute", 0x1008FF12),
+    ("XF86AudioRaiseVolume", 0x1008FF13),
+    ("XF86AudioPlay", 0x1008FF14),
+    ("XF86AudioStop", 0x1008FF15),
+    ("XF86AudioPrev", 0x1008FF16),
+    ("XF86AudioNext", 0x1008FF17),
+    ("XF86HomePage", 0x1008FF18),
+    ("XF86Mail", 0x1008FF19),
+    ("XF86Search", 0x1008FF1B),
+    ("XF86Calculator", 0x1008FF1D),
+    ("XF86Back", 0x1008FF26),
+    ("XF86Forward", 0x1008FF27),
+    ("XF86Stop", 0x1008FF28),
+    ("XF86Refresh", 0x1008FF29),
+    ("XF86Sleep", 0x1008FF2F),
+    ("XF86Favorites", 0x1008FF30),
+    ("XF86AudioMedia", 0x1008FF32),
+    ("XF86MyComputer", 0x1008FF33),
+    ("space", 0x20),
+    ("exclam", 0x21),
+    ("quotedbl", 0x22),
+    ("numbersign", 0x23),
+    ("dollar", 0x24),
+    ("percent", 0x25),
+    ("ampersand", 0x26),
+    ("apostrophe", 0x27),
+    ("parenleft", 0x28),
+    ("parenright", 0x29),
+    ("asterisk", 0x2A),
+    ("plus", 0x2B),
+    ("comma", 0x2C),
+    ("minus", 0x2D),
+    ("period", 0x2E),
+    ("slash", 0x2F),
+    ("colon", 0x3A),
+    ("semicolon", 0x3B),
+    ("less", 0x3C),
+    ("equal", 0x3D),
+    ("greater", 0x3E),
+    ("question", 0x3F),
+    ("at", 0x40),
+    ("bracketleft", 0x5B),
+    ("backslash", 0x5C),
+    ("bracketright", 0x5D),
+    ("asciicircum", 0x5E),
+    ("underscore", 0x5F),
+    ("grave", 0x60),
+    ("braceleft", 0x7B),
+    ("bar", 0x7C),
+    ("braceright", 0x7D),
+    ("asciitilde", 0x7E),
+];
+
+// xdotool names and keysyms without a key of their own
+const ALIASES: [(&str, Key); 6] = [
+    ("control", Key::Ctrl),
+    ("super", Key::LWin),
+    ("meta", Key::Alt),
+    ("Meta_L", Key::LAlt),
+    ("KP_Enter", Key::Enter),
+    ("ISO_Level3_Shift", Key::RAlt),
+];
+
+// a keysym name like `Return`, `a` or `U20AC`, or a key name like `ctrl`
+fn unit_of(name: &str, layout: Option<&Layout>) -> Option<Unit> {
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Some(char_unit(c, layout));
+    }
+
+    let keysym = KEYSYMS
+        .iter()
+        .find(|(keysym_name, _)| *keysym_name == name)
+        .map(|&(_, keysym)| keysym)
+        .or_else(|| {
+            let hex = name.strip_prefix('U')?;
+            u32::from_str_radix(hex, 16).ok().map(|c| 0x0100_0000 + c)
+        });
+    match keysym {
+        // latin-1 and unicode keysyms are typed as chars
+        Some(keysym) if keysym < 0x100 || keysym & 0xFF00_0000 == 0x0100_0000 => {
+            char::from_u32(keysym & 0x00FF_FFFF).map(|c| char_unit(c, layout))
+        }
+        Some(keysym) => Key::from_keysym(keysym).map(|key| Unit::Key(key, Modifiers::NONE)),
+        None => ALIASES
+            .iter()
+            .find(|(alias, _)| *alias == name)
+            .map(|&(_, key)| key)
+            .or_else(|| Key::from_name(&name.to_ascii_lowercase()))
+            .map(|key| Unit::Key(key, Modifiers::NONE)),
+    }
+}
+
+// one chord like `ctrl+alt+t`, at `start` of `s`, like <ctrl+alt+t>
+fn push_chord(
+    s: &str,
+    start: usize,
+    chord: &str,
+    layout: Option<&Layout>,
+    inputs: &mut Vec<KeyInput>,
+    state: &mut KeyState,
+) -> std::result::Result<(), ParseError> {
+    let mut units: Vec<Unit> = Vec::new();
+    let mut offset = start;
+    for name in chord.split('+') {
+        if name.is_empty() {
+            return Err(ParseError::new(s, offset, "", "keysym or key name"));
+        }
+        let unit = unit_of(name, layout)
+            .ok_or_else(|| ParseError::new(s, offset, name, "keysym or key name"))?;
+        units.push(unit);
+        offset += name.len() + 1;
+    }
+
+    let (last, modifiers) = units.split_last().unwrap();
+    let mut mod_keys: Vec<Key> = Vec::new();
+    for unit in modifiers {
+        match unit {
+            Unit::Key(key, _) => mod_keys.push(*key),
+            // not on the layout, the chord is typed without it
+            Unit::Unicode(_) => {}
+        }
+    }
+
+    typed_modifiers_up(inputs, state);
+    match *last {
+        Unit::Key(key, char_modifiers) if mod_keys.is_empty() => {
+            push_key_down_and_up(key, char_modifiers, inputs, state)
+        }
+        Unit::Key(key, char_modifiers) => {
+            mod_keys.extend(char_modifier_keys(char_modifiers, &mod_keys));
+            push_combination(&mod_keys, key, 1, None, inputs, state);
+        }
+        Unit::Unicode(c) => push_dead_keys_or_unicode(c, layout, inputs, state),
+    }
+    Ok(())
+}
+
+/// Parse xdotool key chords separated by spaces, like `ctrl+alt+t super+Return`,
+/// into the key inputs of the matching key string
+pub fn parse_xdo(s: &str) -> std::result::Result<Vec<KeyInput>, ParseError> {
+    let layout = layout();
+    let mut inputs = parse_xdo_with(s, layout.as_deref())?;
+    let balance = *BALANCE.read().unwrap();
+    balance_keys(s, &mut inputs, balance, layout.as_deref())?;
+    Ok(inputs)
+}
+
+// parse with `layout`, None for the system layout
+pub(super) fn parse_xdo_with(
+    s: &str,
+    layout: Option<&Layout>,
+) -> std::result::Result<Vec<KeyInput>, ParseError> {
+    let mut result: Vec<KeyInput> = Vec::new();
+    let mut state = KeyState::new();
+    let mut start = 0;
+    for chord in s.split(|c: char| c.is_ascii_whitespace()) {
+        if !chord.is_empty() {
+            push_chord(s, start, chord, layout, &mut result, &mut state)?;
+        }
+        start += chord.len() + 1;
+    }
+    typed_modifiers_up(&mut result, &mut state);
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keyboard::parse_str_with;
+
+    #[test]
+    fn same_as_key_strings() {
+        let us = Layout::builtin("us").unwrap();
+        for (xdo, native) in [
+            ("ctrl+alt+t", "<ctrl+alt+t>"),
+            ("super+Return", "<win+enter>"),
+            ("shift+Tab", "<shift+tab>"),
+            (
+                "BackSpace Prior  Next\tDelete",
+                "<bs><pageup><pagedown><del>",
+            ),
+            ("Control_L+Shift_R+KP_5 F4", "<lctrl+rshift+numpad5><f4>"),
+            ("A exclam a", "<A><!><a>"),
+            ("ctrl+A", "<ctrl+shift+a>"),
+            ("ctrl+plus XF86AudioMute", "<ctrl+shift+=><volume_mute>"),
+            ("U4E2D Menu", "<u:4e2d><apps>"),
+        ] {
+            assert_eq!(
+                parse_str_with(native, Some(&us)).unwrap(),
+                parse_xdo_with(xdo, Some(&us)).unwrap(),
+                "{} and {}",
+                xdo,
+                native
+            );
+        }
+    }
+
+    #[test]
+    fn xdo_errors() {
+        let us = Layout::builtin("us").unwrap();
+        let err = |s: &str| parse_xdo_with(s, Some(&us)).unwrap_err();
+
+        let e = err("ctrl+c ctrl+Retrun");
+        assert_eq!((12, "Retrun"), (e.offset, e.fragment.as_str()));
+        assert_eq!(5, err("ctrl+").offset);
+        assert_eq!(0, err("+a").offset);
+    }
+}
@@ -0,0 +1,846 @@
+use std::{collections::HashMap, fmt, ops::BitOr, path::Path, str::FromStr};
+
+use crate::{
+    errors::{Result, ResultExt},
+    key::Key,
+};
+
+/// A set of modifier keys held while pressing a key
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Modifiers(pub u8);
+
+impl Modifiers {
+    pub const NONE: Modifiers = Modifiers(0);
+    pub const SHIFT: Modifiers = Modifiers(0x01);
+    pub const CTRL: Modifiers = Modifiers(0x02);
+    pub const ALT: Modifiers = Modifiers(0x04);
+    /// AltGr, the third level key, sent as right alt
+    pub const ALTGR: Modifiers = Modifiers(0x08);
+
+    pub fn contains(self, other: Modifiers) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn insert(&mut self, other: Modifiers) {
+        self.0 |= other.0;
+    }
+
+    pub fn remove(&mut self, other: Modifiers) {
+        self.0 &= !other.0;
+    }
+}
+
+impl BitOr for Modifiers {
+    type Output = Modifiers;
+
+    fn bitor(self, rhs: Modifiers) -> Modifiers {
+        Modifiers(self.0 | rhs.0)
+    }
+}
+
+impl fmt::Display for Modifiers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names = [
+            (Modifiers::CTRL, "ctrl"),
+            (Modifiers::ALT, "alt"),
+            (Modifiers::ALTGR, "altgr"),
+            (Modifiers::SHIFT, "shift"),
+        ];
+        let names: Vec<&str> = names
+            .iter()
+            .filter(|(m, _)| self.contains(*m) && !m.is_empty())
+            .map(|(_, name)| *name)
+            .collect();
+        write!(f, "{}", names.join("+"))
+    }
+}
+
+// modifiers of the shift levels, in lookup priority order
+const LEVELS: [Modifiers; 4] = [
+    Modifiers::NONE,
+    Modifiers::SHIFT,
+    Modifiers::ALTGR,
+    Modifiers(Modifiers::SHIFT.0 | Modifiers::ALTGR.0),
+];
+
+/// A dead key, typing nothing itself but an accent on the next char, like
+/// `^` then `e` for `ê`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DeadKey {
+    Grave,
+    Acute,
+    Circumflex,
+    Tilde,
+    Diaeresis,
+    Cedilla,
+    Ring,
+}
+
+// dead keys, their XKB keysym name, the char typed by the dead key then
+// space, the char after the X11 compose key, and base and composed char pairs
+const DEAD_KEYS: [(DeadKey, &str, char, char, &str); 7] = [
+    (
+        DeadKey::Grave,
+        "dead_grave",
+        '`',
+        '`',
+        "aàeèiìoòuùAÀEÈIÌOÒUÙ",
+    ),
+    (
+        DeadKey::Acute,
+        "dead_acute",
+        '´',
+        '\'',
+        "aáeéiíoóuúyýAÁEÉIÍOÓUÚYÝ",
+    ),
+    (
+        DeadKey::Circumflex,
+        "dead_circumflex",
+        '^',
+        '^',
+        "aâeêiîoôuûAÂEÊIÎOÔUÛ",
+    ),
+    (DeadKey::Tilde, "dead_tilde", '~', '~', "aãnñoõAÃNÑOÕ"),
+    (
+        DeadKey::Diaeresis,
+        "dead_diaeresis",
+        '¨',
+        '"',
+        "aäeëiïoöuüyÿAÄEËIÏOÖUÜYŸ",
+    ),
+    (DeadKey::Cedilla, "dead_cedilla", '¸', ',', "cçCÇ"),
+    (DeadKey::Ring, "dead_abovering", '°', 'o', "aåAÅ"),
+];
+
+impl DeadKey {
+    /// The dead key of a XKB keysym name, like `dead_circumflex`
+    pub fn from_name(name: &str) -> Option<DeadKey> {
+        DEAD_KEYS
+            .iter()
+            .find(|(_, dead_name, ..)| *dead_name == name)
+            .map(|&(dead, ..)| dead)
+    }
+
+    fn entry(self) -> &'static (DeadKey, &'static str, char, char, &'static str) {
+        DEAD_KEYS.iter().find(|(dead, ..)| *dead == self).unwrap()
+    }
+
+    /// The char typed by the dead key then space, like `^`
+    pub fn spacing(self) -> char {
+        self.entry().2
+    }
+
+    /// The char typed after the X11 compose key (`Multi_key`) for this
+    /// accent, like `^` of `Multi_key ^ e`
+    pub fn compose_char(self) -> char {
+        self.entry().3
+    }
+
+    /// The char typed by the dead key then `base`, like `ê` for `e`
+    pub fn compose(self, base: char) -> Option<char> {
+        if base == ' ' {
+            return Some(self.spacing());
+        }
+        let pairs: Vec<char> = self.entry().4.chars().collect();
+        pairs
+            .chunks(2)
+            .find(|pair| pair[0] == base)
+            .map(|pair| pair[1])
+    }
+}
+
+/// The dead key and the base char typing `c`, like circumflex and `e` for
+/// `ê`, or circumflex and space for `^`
+pub fn decompose(c: char) -> Option<(DeadKey, char)> {
+    for &(dead, _, spacing, _, pairs) in DEAD_KEYS.iter() {
+        if c == spacing {
+            return Some((dead, ' '));
+        }
+        let pairs: Vec<char> = pairs.chars().collect();
+        if let Some(pair) = pairs.chunks(2).find(|pair| pair[1] == c) {
+            return Some((dead, pair[0]));
+        }
+    }
+    None
+}
+
+// what a key types at a level
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Level {
+    Char(char),
+    Dead(DeadKey),
+}
+
+type Row = (Key, [Option<Level>; 4]);
+
+/// A keyboard layout, maps chars to the key and modifiers typing them.
+///
+/// Keys are physical positions, so the same string gives the same key events
+/// on every OS, whatever the active system layout is.
+#[derive(Debug, Clone)]
+pub struct Layout {
+    name: String,
+    chars: HashMap<char, (Key, Modifiers)>,
+    keys: HashMap<(Key, Modifiers), char>,
+    // chars only typed by a dead key then another key
+    dead_chars: HashMap<char, [(Key, Modifiers); 2]>,
+}
+
+const BUILTIN: &[(&[&str], &str)] = &[
+    (&["us"], include_str!("layouts/us.txt")),
+    (&["uk", "gb"], include_str!("layouts/uk.txt")),
+    (&["de"], include_str!("layouts/de.txt")),
+    (&["fr"], include_str!("layouts/fr.txt")),
+];
+
+impl Layout {
+    fn from_rows(name: &str, rows: &[Row]) -> Layout {
+        let mut chars = HashMap::from([
+            ('\t', (Key::Tab, Modifiers::NONE)),
+            ('\n', (Key::Enter, Modifiers::NONE)),
+            ('\r', (Key::Enter, Modifiers::NONE)),
+        ]);
+        let mut dead_keys: Vec<(DeadKey, (Key, Modifiers))> = Vec::new();
+        // lower levels win when a char is on several keys
+        for (level, modifiers) in LEVELS.iter().enumerate() {
+            for (key, levels) in rows {
+                match levels[level] {
+                    Some(Level::Char(c)) => {
+                        chars.entry(c).or_insert((*key, *modifiers));
+                    }
+                    Some(Level::Dead(dead)) if !dead_keys.iter().any(|(d, _)| *d == dead) => {
+                        dead_keys.push((dead, (*key, *modifiers)));
+                    }
+                    _ => {}
+                }
+            }
+        }
+        let keys = chars
+            .iter()
+            .filter(|(c, _)| !c.is_control())
+            .map(|(c, key_mods)| (*key_mods, *c))
+            .collect();
+
+        let mut dead_chars = HashMap::new();
+        for (dead, dead_key) in dead_keys {
+            let (_, _, spacing, _, pairs) = dead.entry();
+            let pairs: Vec<char> = pairs.chars().collect();
+            let spacing_pair = [' ', *spacing];
+            for pair in pairs.chunks(2).chain([&spacing_pair[..]]) {
+                if chars.contains_key(&pair[1]) {
+                    continue;
+                }
+                if let Some(&base_key) = chars.get(&pair[0]) {
+                    dead_chars.entry(pair[1]).or_insert([dead_key, base_key]);
+                }
+            }
+        }
+        Layout {
+            name: name.to_string(),
+            chars,
+            keys,
+            dead_chars,
+        }
+    }
+
+    /// Builtin layouts: `us`, `uk` (or `gb`), `de` and `fr`
+    pub fn builtin(name: &str) -> Result<Layout> {
+        for (names, data) in BUILTIN {
+            if names.contains(&name) {
+                return Layout::parse(name, data);
+            }
+        }
+        bail!("unknown layout {}", name)
+    }
+
+    /// Parse the layout text format, one key per line:
+    ///
+    /// ```text
+    /// # position  level1  level2  [altgr  shift+altgr]
+    /// Digit2      2       "       ²
+    /// Space       U+0020  U+0020
+    /// ```
+    ///
+    /// position is a `Key` variant name, a level is a single char, `U+XXXX`
+    /// for a code point, a dead key like `dead_circumflex` or `--` for
+    /// nothing.
+    pub fn parse(name: &str, data: &str) -> Result<Layout> {
+        let mut rows: Vec<Row> = Vec::new();
+        for (n, line) in data.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut fields = line.split_whitespace();
+            let key = fields.next().unwrap();
+            let key = Key::from_str(key).chain_err(|| format!("layout line {}", n + 1))?;
+            let mut levels = [None; 4];
+            for (i, field) in fields.enumerate() {
+                if i >= levels.len() {
+                    bail!("layout line {}: more than {} levels", n + 1, levels.len());
+                }
+                levels[i] = parse_level(field)
+                    .chain_err(|| format!("layout line {}: bad level {}", n + 1, field))?;
+            }
+            rows.push((key, levels));
+        }
+        Ok(Layout::from_rows(name, &rows))
+    }
+
+    /// Parse the `key <AE01> { [ 1, exclam ] };` entries of a XKB symbols
+    /// file, e.g. `/usr/share/X11/xkb/symbols/de`.
+    ///
+    /// Only the default variant and the first group of each key are read, and
+    /// keysyms without a char or dead key are skipped. `include` statements
+    /// are followed by `load` only, symbols without a char on every digit and
+    /// letter key are an error.
+    pub fn parse_xkb(name: &str, data: &str) -> Result<Layout> {
+        Layout::from_xkb(name, data, None)
+    }
+
+    // includes are read from `dir`, when given
+    fn from_xkb(name: &str, data: &str, dir: Option<&Path>) -> Result<Layout> {
+        let mut rows: Vec<Row> = Vec::new();
+        xkb_rows(&strip_xkb_comments(data), None, dir, 0, &mut rows)?;
+        if rows.is_empty() {
+            bail!("no key found in XKB symbols");
+        }
+        let missing: Vec<String> = alphanumeric_keys()
+            .filter(|key| {
+                !rows
+                    .iter()
+                    .any(|(k, levels)| k == key && levels[0].is_some())
+            })
+            .map(|key| format!("{:?}", key))
+            .collect();
+        if !missing.is_empty() {
+            let hint = match dir {
+                Some(_) => "",
+                None => ", includes are only followed when loaded from a file",
+            };
+            bail!("no char on {} in XKB symbols{}", missing.join(", "), hint);
+        }
+        Ok(Layout::from_rows(name, &rows))
+    }
+
+    /// Load a layout file, either the layout text format or XKB symbols. XKB
+    /// includes are read from the directory of the file.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Layout> {
+        let path = path.as_ref();
+        let data = std::fs::read_to_string(path)?;
+        let name = path
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        if data.contains("key <") || data.contains("include \"") {
+            Layout::from_xkb(&name, &data, path.parent())
+        } else {
+            Layout::parse(&name, &data)
+        }
+        .chain_err(|| format!("load layout {} failed", path.display()))
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The key and modifiers typing `c`, or None if `c` is not on this layout
+    pub fn lookup(&self, c: char) -> Option<(Key, Modifiers)> {
+        self.chars.get(&c).copied()
+    }
+
+    /// The char typed by `key` with `modifiers`, the reverse of `lookup`
+    pub fn char_of(&self, key: Key, modifiers: Modifiers) -> Option<char> {
+        self.keys.get(&(key, modifiers)).copied()
+    }
+
+    /// The dead key and the key typing `c` after it, like `^` then `e` for
+    /// `ê`, None if `c` is on a key of its own or not reachable that way
+    pub fn dead_key_sequence(&self, c: char) -> Option<[(Key, Modifiers); 2]> {
+        self.dead_chars.get(&c).copied()
+    }
+
+    /// The chars typed by a dead key then another key, and their keys
+    pub fn dead_key_sequences(&self) -> impl Iterator<Item = (char, [(Key, Modifiers); 2])> + '_ {
+        self.dead_chars.iter().map(|(c, keys)| (*c, *keys))
+    }
+}
+
+// the `[ ... ]` keysym list of a key body, skipping indexes like `type[Group1]`
+// deeper includes are most likely a cycle
+const MAX_XKB_INCLUDE_DEPTH: usize = 16;
+
+// the body of the XKB symbols `section`, the default one for None, which is
+// the one marked `default` or else the first
+fn xkb_section<'a>(data: &'a str, section: Option<&str>) -> Option<&'a str> {
+    let starts: Vec<usize> = data
+        .match_indices("xkb_symbols")
+        .map(|(at, _)| at)
+        .collect();
+    if starts.is_empty() {
+        return section.is_none().then_some(data);
+    }
+    let mut bodies = starts.iter().enumerate().map(|(i, &start)| {
+        let end = starts.get(i + 1).copied().unwrap_or(data.len());
+        let line_start = data[..start].rfind('\n').map_or(0, |at| at + 1);
+        let flags = &data[line_start..start];
+        let header_end = data[start..end].find('{').map_or(end, |at| start + at);
+        let name = data[start..header_end].split('"').nth(1).unwrap_or("");
+        (flags, name, &data[header_end..end])
+    });
+    match section {
+        Some(section) => bodies.find(|(_, name, _)| *name == section),
+        None => bodies
+            .clone()
+            .find(|(flags, _, _)| flags.contains("default"))
+            .or_else(|| bodies.next()),
+    }
+    .map(|(_, _, body)| body)
+}
+
+// add the keys of `section` to `rows` in order, those of includes read from
+// `dir` too, skipped without a `dir`
+fn xkb_rows(
+    data: &str,
+    section: Option<&str>,
+    dir: Option<&Path>,
+    depth: usize,
+    rows: &mut Vec<Row>,
+) -> Result<()> {
+    let mut rest = match xkb_section(data, section) {
+        Some(body) => body,
+        None => bail!("no XKB symbols {:?}", section.unwrap_or_default()),
+    };
+    loop {
+        let next = [rest.find("key <"), rest.find("include \"")]
+            .into_iter()
+            .flatten()
+            .min();
+        let start = match next {
+            Some(start) => start,
+            None => return Ok(()),
+        };
+        if rest[start..].starts_with("include") {
+            rest = &rest[start + "include \"".len()..];
+            let end = match rest.find('"') {
+                Some(end) => end,
+                None => bail!("unterminated XKB include"),
+            };
+            if let Some(dir) = dir {
+                // like "latin(type4)" or "pc+us", other groups like "ru:2" are skipped
+                for part in rest[..end].split(['+', '|']) {
+                    if !part.is_empty() && !part.contains(':') {
+                        include_xkb_rows(part, dir, depth + 1, rows)?;
+                    }
+                }
+            }
+            rest = &rest[end..];
+            continue;
+        }
+
+        rest = &rest[start + "key <".len()..];
+        let end = match rest.find('>') {
+            Some(end) => end,
+            None => bail!("unterminated XKB key name"),
+        };
+        let xkb_name = &rest[..end];
+        rest = &rest[end..];
+
+        let body_end = rest.find(';').unwrap_or(rest.len());
+        let body = &rest[..body_end];
+        let (open, close) = match symbols_list(body) {
+            Some(list) => list,
+            None => continue,
+        };
+        let key = match xkb_key(xkb_name) {
+            Some(key) => key,
+            None => continue,
+        };
+        let mut levels = [None; 4];
+        for (level, sym) in levels.iter_mut().zip(body[open + 1..close].split(',')) {
+            let sym = sym.trim();
+            *level = match DeadKey::from_name(sym) {
+                Some(dead) => Some(Level::Dead(dead)),
+                None => keysym_char(sym).map(Level::Char),
+            };
+        }
+        rows.retain(|(k, _)| *k != key);
+        rows.push((key, levels));
+    }
+}
+
+// XKB symbols without their `//` comments, which may mention keys and includes
+fn strip_xkb_comments(data: &str) -> String {
+    data.lines()
+        .map(|line| line.split("//").next().unwrap())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// the rows of an include like `latin(type4)`, the file `latin` of `dir`
+fn include_xkb_rows(include: &str, dir: &Path, depth: usize, rows: &mut Vec<Row>) -> Result<()> {
+    if depth > MAX_XKB_INCLUDE_DEPTH {
+        bail!("XKB includes nested deeper than {}", MAX_XKB_INCLUDE_DEPTH);
+    }
+    let (file, section) = match include.split_once('(') {
+        Some((file, section)) => (file, Some(section.trim_end_matches(')'))),
+        None => (include, None),
+    };
+    let path = dir.join(file);
+    let data = std::fs::read_to_string(&path)
+        .chain_err(|| format!("XKB include {} failed", path.display()))?;
+    xkb_rows(&strip_xkb_comments(&data), section, Some(dir), depth, rows)
+        .chain_err(|| format!("XKB include {} failed", include))
+}
+
+fn symbols_list(body: &str) -> Option<(usize, usize)> {
+    let mut from = 0;
+    loop {
+        let open = from + body[from..].find('[')?;
+        let close = open + body[open..].find(']')?;
+        let indexed = body[..open]
+            .chars()
+            .next_back()
+            .is_some_and(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !indexed {
+            return Some((open, close));
+        }
+        from = close;
+    }
+}
+
+fn parse_level(s: &str) -> Result<Option<Level>> {
+    if s == "--" {
+        return Ok(None);
+    }
+    if let Some(hex) = s.strip_prefix("U+") {
+        return match u32::from_str_radix(hex, 16).ok().and_then(char::from_u32) {
+            Some(c) => Ok(Some(Level::Char(c))),
+            None => bail!("invalid code point {}", s),
+        };
+    }
+    if let Some(dead) = DeadKey::from_name(s) {
+        return Ok(Some(Level::Dead(dead)));
+    }
+    let mut chars = s.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Ok(Some(Level::Char(c))),
+        _ => bail!("expected a single char or dead key"),
+    }
+}
+
+// XKB key names of the alphanumeric block, by row
+fn xkb_key(name: &str) -> Option<Key> {
+    const AE: [Key; 12] = [
+        Key::Digit1,
+        Key::Digit2,
+        Key::Digit3,
+        Key::Digit4,
+        Key::Digit5,
+        Key::Digit6,
+        Key::Digit7,
+        Key::Digit8,
+        Key::Digit9,
+        Key::Digit0,
+        Key::Minus,
+        Key::Equal,
+    ];
+    const AD: [Key; 12] = [
+        Key::Q,
+        Key::W,
+        Key::E,
+        Key::R,
+        Key::T,
+        Key::Y,
+        Key::U,
+        Key::I,
+        Key::O,
+        Key::P,
+        Key::LeftBracket,
+        Key::RightBracket,
+    ];
+    const AC: [Key; 12] = [
+        Key::A,
+        Key::S,
+        Key::D,
+        Key::F,
+        Key::G,
+        Key::H,
+        Key::J,
+        Key::K,
+        Key::L,
+        Key::Semicolon,
+        Key::Quote,
+        Key::Backslash,
+    ];
+    const AB: [Key; 10] = [
+        Key::Z,
+        Key::X,
+        Key::C,
+        Key::V,
+        Key::B,
+        Key::N,
+        Key::M,
+        Key::Comma,
+        Key::Period,
+        Key::Slash,
+    ];
+    match name {
+        "TLDE" => return Some(Key::Backquote),
+        "BKSL" => return Some(Key::Backslash),
+        "LSGT" => return Some(Key::IntlBackslash),
+        "SPCE" => return Some(Key::Space),
+        _ => {}
+    }
+    if name.len() != 4 || !name.is_char_boundary(2) {
+        return None;
+    }
+    let row: &[Key] = match &name[..2] {
+        "AE" => &AE,
+        "AD" => &AD,
+        "AC" => &AC,
+        "AB" => &AB,
+        _ => return None,
+    };
+    let index: usize = name[2..].parse().ok()?;
+    row.get(index.checked_sub(1)?).copied()
+}
+
+// the digit and letter keys, <AE01> to <AE10>, <AD01> to <AD10>, <AC01> to
+// <AC09> and <AB01> to <AB07>
+fn alphanumeric_keys() -> impl Iterator<Item = Key> {
+    [("AE", 10), ("AD", 10), ("AC", 9), ("AB", 7)]
+        .into_iter()
+        .flat_map(|(row, len)| (1..=len).filter_map(move |i| xkb_key(&format!("{}{:02}", row, i))))
+}
+
+// keysym names of 0x20..=0x7E and 0xA0..=0xFF, where keysym equals the code point
+const ASCII_KEYSYMS: &str = "space exclam quotedbl numbersign dollar percent ampersand \
+    apostrophe parenleft parenright asterisk plus comma minus period slash \
+    0 1 2 3 4 5 6 7 8 9 colon semicolon less equal greater question at \
+    A B C D E F G H I J K L M N O P Q R S T U V W X Y Z \
+    bracketleft backslash bracketright asciicircum underscore grave \
+    a b c d e f g h i j k l m n o p q r s t u v w x y z \
+    braceleft bar braceright asciitilde";
+const LATIN1_KEYSYMS: &str = "nobreakspace exclamdown cent sterling currency yen brokenbar \
+    section diaeresis copyright ordfeminine guillemotleft notsign hyphen registered macron \
+    degree plusminus twosuperior threesuperior acute mu paragraph periodcentered cedilla \
+    onesuperior masculine guillemotright onequarter onehalf threequarters questiondown \
+    Agrave Aacute Acircumflex Atilde Adiaeresis Aring AE Ccedilla Egrave Eacute Ecircumflex \
+    Ediaeresis Igrave Iacute Icircumflex Idiaeresis ETH Ntilde Ograve Oacute Ocircumflex \
+    Otilde Odiaeresis multiply Oslash Ugrave Uacute Ucircumflex Udiaeresis Yacute THORN \
+    ssharp agrave aacute acircumflex atilde adiaeresis aring ae ccedilla egrave eacute \
+    ecircumflex ediaeresis igrave iacute icircumflex idiaeresis eth ntilde ograve oacute \
+    ocircumflex otilde odiaeresis division oslash ugrave uacute ucircumflex udiaeresis \
+    yacute thorn ydiaeresis";
+
+lazy_static! {
+    static ref KEYSYM_NAME_TO_CHAR: HashMap<&'static str, char> = {
+        let ascii = ASCII_KEYSYMS.split_whitespace().zip('\u{20}'..='\u{7E}');
+        let latin1 = LATIN1_KEYSYMS.split_whitespace().zip('\u{A0}'..='\u{FF}');
+        let mut map: HashMap<&'static str, char> = ascii.chain(latin1).collect();
+        map.insert("EuroSign", '€');
+        map
+    };
+}
+
+// char of a keysym name, like `eacute` or `U00E9`
+fn keysym_char(name: &str) -> Option<char> {
+    if let Some(&c) = KEYSYM_NAME_TO_CHAR.get(name) {
+        return Some(c);
+    }
+    let hex = name.strip_prefix('U').filter(|hex| hex.len() >= 4)?;
+    char::from_u32(u32::from_str_radix(hex, 16).ok()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layout(name: &str) -> Layout {
+        Layout::builtin(name).unwrap()
+    }
+
+    #[test]
+    fn builtin_layouts() {
+        let us = layout("us");
+        assert_eq!(Some((Key::A, Modifiers::NONE)), us.lookup('a'));
+        assert_eq!(Some((Key::Digit2, Modifiers::SHIFT)), us.lookup('@'));
+        assert_eq!(Some((Key::Space, Modifiers::NONE)), us.lookup(' '));
+        assert_eq!(Some((Key::Enter, Modifiers::NONE)), us.lookup('\n'));
+        assert_eq!(None, us.lookup('é'));
+        assert_eq!(Some('@'), us.char_of(Key::Digit2, Modifiers::SHIFT));
+        assert_eq!(None, us.char_of(Key::Space, Modifiers::SHIFT));
+
+        let uk = layout("gb");
+        assert_eq!(Some((Key::Digit2, Modifiers::SHIFT)), uk.lookup('"'));
+        assert_eq!(Some((Key::Quote, Modifiers::SHIFT)), uk.lookup('@'));
+        assert_eq!(Some((Key::E, Modifiers::ALTGR)), uk.lookup('é'));
+
+        let de = layout("de");
+        assert_eq!(Some((Key::Y, Modifiers::NONE)), de.lookup('z'));
+        assert_eq!(Some((Key::Z, Modifiers::SHIFT)), de.lookup('Y'));
+        assert_eq!(Some((Key::Q, Modifiers::ALTGR)), de.lookup('@'));
+        assert_eq!(Some((Key::Minus, Modifiers::NONE)), de.lookup('ß'));
+
+        let fr = layout("fr");
+        assert_eq!(Some((Key::Q, Modifiers::NONE)), fr.lookup('a'));
+        assert_eq!(Some((Key::Digit2, Modifiers::NONE)), fr.lookup('é'));
+        assert_eq!(Some((Key::Digit1, Modifiers::SHIFT)), fr.lookup('1'));
+        assert_eq!(Some((Key::Digit0, Modifiers::ALTGR)), fr.lookup('@'));
+
+        assert!(Layout::builtin("xx").is_err());
+    }
+
+    #[test]
+    fn parse_text() {
+        let l = Layout::parse("t", "# comment\nA  x  U+00C9  --  y\n\nB  y").unwrap();
+        assert_eq!(Some((Key::A, Modifiers::SHIFT)), l.lookup('É'));
+        // lower level wins
+        assert_eq!(Some((Key::B, Modifiers::NONE)), l.lookup('y'));
+        assert!(Layout::parse("t", "Nope a A").is_err());
+        assert!(Layout::parse("t", "A ab").is_err());
+        assert!(Layout::parse("t", "A a b c d e").is_err());
+    }
+
+    #[test]
+    fn parse_xkb() {
+        let sample = r#"
+            default partial alphanumeric_keys
+            xkb_symbols "basic" {
+                include "latin(type4)"
+                name[Group1]="German";
+                key <AE02> { [ 2, quotedbl, twosuperior, oneeighth ] };
+                key <AD01> { [ q, Q, at, Greek_OMEGA ] };
+                key <AB01> { [ y, Y, guillemotright, U203A ] };
+                key <AD11> { [ udiaeresis, Udiaeresis, dead_diaeresis ] };
+                key <AE12> { [ dead_acute, dead_grave ] };
+                key <LSGT> { type[Group1]="FOUR_LEVEL", [ less, greater, bar ] };
+                key <FK01> { [ F1 ] };
+            };
+        "#;
+        // only what `include "latin(type4)"` leaves out
+        let e = Layout::parse_xkb("de", sample).unwrap_err().to_string();
+        assert!(e.contains("Digit1, Digit3"), "{}", e);
+        assert!(e.contains("includes are only followed"), "{}", e);
+
+        // the included keys, overridden by the sample
+        let included: String = alphanumeric_keys()
+            .map(|key| format!("key <{}> {{ [ U2603 ] }};\n", xkb_name(key)))
+            .collect();
+        let data = sample.replacen("name[Group1]", &(included + "name[Group1]"), 1);
+        let l = Layout::parse_xkb("de", &data).unwrap();
+        assert_eq!(Some((Key::Digit2, Modifiers::SHIFT)), l.lookup('"'));
+        assert_eq!(Some((Key::Digit2, Modifiers::ALTGR)), l.lookup('²'));
+        assert_eq!(Some((Key::Q, Modifiers::ALTGR)), l.lookup('@'));
+        assert_eq!(Some((Key::Z, Modifiers::NONE)), l.lookup('y'));
+        assert_eq!(
+            Some((Key::Z, Modifiers::SHIFT | Modifiers::ALTGR)),
+            l.lookup('›')
+        );
+        assert_eq!(Some((Key::LeftBracket, Modifiers::NONE)), l.lookup('ü'));
+        assert_eq!(Some((Key::IntlBackslash, Modifiers::ALTGR)), l.lookup('|'));
+        assert_eq!(
+            Some([(Key::Equal, Modifiers::NONE), (Key::Z, Modifiers::SHIFT)]),
+            l.dead_key_sequence('Ý')
+        );
+        assert!(Layout::parse_xkb("x", "nothing").is_err());
+    }
+
+    #[test]
+    fn load_xkb_include() {
+        let dir = std::env::temp_dir().join(format!("hotkeyz-xkb-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let latin: String = alphanumeric_keys()
+            .map(|key| format!("    key <{}> {{ [ U2603 ] }};\n", xkb_name(key)))
+            .collect();
+        let latin = format!(
+            "xkb_symbols \"basic\" {{\n    key <AE02> {{ [ 1 ] }};\n}};\n\
+             xkb_symbols \"type4\" {{\n{}}};\n",
+            latin
+        );
+        std::fs::write(dir.join("latin"), latin).unwrap();
+        std::fs::write(
+            dir.join("mine"),
+            r#"
+                // include "missing"
+                default xkb_symbols "basic" {
+                    include "latin(type4)"
+                    key <AE02> { [ 2, quotedbl ] };
+                };
+                xkb_symbols "other" {
+                    include "missing"
+                };
+            "#,
+        )
+        .unwrap();
+
+        let l = Layout::load(dir.join("mine"));
+        std::fs::remove_dir_all(&dir).unwrap();
+        let l = l.unwrap();
+        assert_eq!(Some((Key::Digit2, Modifiers::SHIFT)), l.lookup('"'));
+        assert_eq!(Some((Key::Digit2, Modifiers::NONE)), l.lookup('2'));
+        assert!(l.lookup('☃').is_some());
+        // not followed without a directory
+        assert!(Layout::parse_xkb("mine", "include \"latin(type4)\"").is_err());
+    }
+
+    // the XKB name of a digit or letter key
+    fn xkb_name(key: Key) -> String {
+        ["AE", "AD", "AC", "AB"]
+            .iter()
+            .flat_map(|row| (1..=12).map(move |i| format!("{}{:02}", row, i)))
+            .find(|name| xkb_key(name) == Some(key))
+            .unwrap()
+    }
+
+    #[test]
+    fn dead_keys() {
+        assert_eq!(Some((DeadKey::Circumflex, 'e')), decompose('ê'));
+        assert_eq!(Some((DeadKey::Acute, ' ')), decompose('´'));
+        assert_eq!(None, decompose('e'));
+        assert_eq!(Some('Ñ'), DeadKey::Tilde.compose('N'));
+        assert_eq!(Some('¨'), DeadKey::Diaeresis.compose(' '));
+        assert_eq!(None, DeadKey::Cedilla.compose('x'));
+
+        let de = layout("de");
+        assert_eq!(
+            Some([(Key::Backquote, Modifiers::NONE), (Key::E, Modifiers::NONE)]),
+            de.dead_key_sequence('ê')
+        );
+        assert_eq!(
+            Some([
+                (Key::Backquote, Modifiers::NONE),
+                (Key::Space, Modifiers::NONE)
+            ]),
+            de.dead_key_sequence('^')
+        );
+        // on a key of its own
+        assert_eq!(None, de.dead_key_sequence('ü'));
+        assert_eq!(None, de.dead_key_sequence('ñ'));
+
+        let fr = layout("fr");
+        assert_eq!(
+            Some([
+                (Key::LeftBracket, Modifiers::SHIFT),
+                (Key::E, Modifiers::NONE)
+            ]),
+            fr.dead_key_sequence('ë')
+        );
+        assert_eq!(None, fr.dead_key_sequence('^'));
+        assert!(Layout::parse("t", "A dead_nothing").is_err());
+    }
+
+    #[test]
+    fn modifiers_display() {
+        assert_eq!("", Modifiers::NONE.to_string());
+        assert_eq!(
+            "altgr+shift",
+            (Modifiers::SHIFT | Modifiers::ALTGR).to_string()
+        );
+    }
+}
@@ -10,21 +10,45 @@ mod errors {
     error_chain! {
       foreign_links {
         Io(::std::io::Error);
+        Json(::serde_json::Error);
         WinOs(::windows::core::Error) #[cfg(windows)];
       }
+
+      errors {
+        Parse(e: crate::keyboard::ParseError) {
+          description("invalid key string")
+          display("{}", e)
+        }
+      }
     }
 }
 
+/// input backends, where keyboard and mouse inputs are sent
+pub mod backend;
+
 /// exports for C ABI
-/// 
+///
 /// Most function return c_int, 0 means success, -1 means failure
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
 pub mod exports;
 
+/// platform neutral key codes
+pub mod key;
+
 /// keyboard related
 pub mod keyboard;
 
+/// keyboard layouts, which key and modifiers type a char
+pub mod layout;
+
 /// mouse related
 pub mod mouse;
 
+/// macros recorded from the real keyboard and mouse
+pub mod recorder;
+
+mod rng;
+
 /// window related
-pub mod window;
\ No newline at end of file
+#[cfg(windows)]
+pub mod window;
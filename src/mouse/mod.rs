@@ -0,0 +1,201 @@
+use crate::{
+    backend::{self, Input},
+    errors::Result,
+    keyboard::KeyInput,
+};
+
+mod path;
+
+pub use path::{path_points, Easing, MovePath, PathShape};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MouseButton {
+    ButtonLeft = 0,
+    ButtonRight = 1,
+    ButtonMiddle = 2,
+    ButtonX1 = 3,
+    ButtonX2 = 4,
+}
+
+impl MouseButton {
+    pub fn from_i32(i: i32) -> Option<MouseButton> {
+        match i {
+            0 => Some(MouseButton::ButtonLeft),
+            1 => Some(MouseButton::ButtonRight),
+            2 => Some(MouseButton::ButtonMiddle),
+            3 => Some(MouseButton::ButtonX1),
+            4 => Some(MouseButton::ButtonX2),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PressType {
+    PressDown = 1,
+    PressUp = 2,
+    Click = 3,
+}
+
+impl PressType {
+    pub fn from_i32(i: i32) -> Option<PressType> {
+        match i {
+            1 => Some(PressType::PressDown),
+            2 => Some(PressType::PressUp),
+            3 => Some(PressType::Click),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MouseInput {
+    MoveTo(i32, i32),
+    MoveDelta(i32, i32),
+    Press(MouseButton, PressType),
+    Wheel(i32),
+    HWheel(i32),
+}
+
+pub fn input_mouses(mouse_inputs: &[MouseInput]) -> Result<()> {
+    let inputs: Vec<Input> = mouse_inputs.iter().map(|m| Input::Mouse(*m)).collect();
+    backend::input(&inputs)
+}
+
+pub fn move_to(x: i32, y: i32) -> Result<()> {
+    input_mouses(&[MouseInput::MoveTo(x, y)])
+}
+
+/// Move the cursor to `x`, `y` along `path`, from where it is, with a move
+/// per point so hover and mouseenter happen on the way
+pub fn move_to_with(x: i32, y: i32, path: &MovePath) -> Result<()> {
+    let from = backend::current().cursor_pos()?;
+    backend::input(&path_inputs(from, (x, y), path))
+}
+
+// a move per point of the path, with the interval of the path between them
+fn path_inputs(from: (i32, i32), to: (i32, i32), path: &MovePath) -> Vec<Input> {
+    let points = path_points(from, to, path);
+    let interval = Input::Key(KeyInput::Delay(path.interval()));
+    let mut inputs = Vec::with_capacity(points.len() * 2);
+    for (i, (x, y)) in points.into_iter().enumerate() {
+        if i > 0 {
+            inputs.push(interval);
+        }
+        inputs.push(Input::Mouse(MouseInput::MoveTo(x, y)));
+    }
+    inputs
+}
+
+pub fn move_delta(x: i32, y: i32) -> Result<()> {
+    input_mouses(&[MouseInput::MoveDelta(x, y)])
+}
+
+pub fn left_click() -> Result<()> {
+    input_mouses(&[MouseInput::Press(MouseButton::ButtonLeft, PressType::Click)])
+}
+
+pub fn left_down() -> Result<()> {
+    input_mouses(&[MouseInput::Press(
+        MouseButton::ButtonLeft,
+        PressType::PressDown,
+    )])
+}
+
+pub fn left_up() -> Result<()> {
+    input_mouses(&[MouseInput::Press(
+        MouseButton::ButtonLeft,
+        PressType::PressUp,
+    )])
+}
+
+pub fn right_click() -> Result<()> {
+    input_mouses(&[MouseInput::Press(
+        MouseButton::ButtonRight,
+        PressType::Click,
+    )])
+}
+
+pub fn middle_click() -> Result<()> {
+    input_mouses(&[MouseInput::Press(
+        MouseButton::ButtonMiddle,
+        PressType::Click,
+    )])
+}
+
+pub fn wheel(amount: i32) -> Result<()> {
+    input_mouses(&[MouseInput::Wheel(amount)])
+}
+
+pub fn hwheel(amount: i32) -> Result<()> {
+    input_mouses(&[MouseInput::HWheel(amount)])
+}
+
+pub fn button_press(btn: MouseButton, press: PressType) -> Result<()> {
+    input_mouses(&[MouseInput::Press(btn, press)])
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Duration};
+
+    use super::*;
+    use crate::backend::{with_backend, RecordingBackend};
+
+    #[test]
+    fn test_move_to() {
+        move_to(0, 0).unwrap();
+    }
+
+    #[test]
+    fn test_move_delta() {
+        move_delta(100, 100).unwrap();
+    }
+
+    #[test]
+    fn test_left_click() {
+        left_click().unwrap();
+    }
+
+    #[test]
+    fn test_select_square() {
+        input_mouses(&[
+            MouseInput::Press(MouseButton::ButtonLeft, PressType::PressDown),
+            MouseInput::MoveDelta(100, 100),
+            MouseInput::Press(MouseButton::ButtonLeft, PressType::PressUp),
+        ])
+        .unwrap()
+    }
+
+    #[test]
+    fn move_along_path() -> Result<()> {
+        let rec = Arc::new(RecordingBackend::new());
+        let path = MovePath {
+            shape: PathShape::Line,
+            easing: Easing::Linear,
+            duration: Duration::from_millis(30),
+            rate: 100,
+            seed: Some(1),
+        };
+        let delay = Input::Key(KeyInput::Delay(Duration::from_millis(10)));
+        let to = |x, y| Input::Mouse(MouseInput::MoveTo(x, y));
+        assert_eq!(
+            vec![to(10, 20), delay, to(20, 40), delay, to(30, 60)],
+            path_inputs((0, 0), (30, 60), &path)
+        );
+
+        // the cursor position is not known yet
+        assert!(with_backend(rec.clone(), || move_to_with(30, 60, &path)).is_err());
+
+        rec.set_cursor_pos(0, 0);
+        let start = std::time::Instant::now();
+        with_backend(rec.clone(), || move_to_with(30, 60, &path))?;
+        // the delays between the moves are slept
+        assert!(start.elapsed() >= Duration::from_millis(20));
+        assert_eq!(vec![to(10, 20), to(20, 40), to(30, 60)], rec.take());
+        // from where the last move left the cursor
+        with_backend(rec.clone(), || move_to_with(0, 0, &path))?;
+        assert_eq!(Some(&to(20, 40)), rec.take().first());
+        Ok(())
+    }
+}
@@ -0,0 +1,313 @@
+use std::time::Duration;
+
+use crate::rng::Rng;
+
+/// How the speed changes along a path
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Easing {
+    Linear = 0,
+    /// starts slow
+    EaseIn = 1,
+    /// ends slow
+    EaseOut = 2,
+    /// starts and ends slow
+    EaseInOut = 3,
+}
+
+impl Easing {
+    pub fn from_i32(i: i32) -> Option<Easing> {
+        match i {
+            0 => Some(Easing::Linear),
+            1 => Some(Easing::EaseIn),
+            2 => Some(Easing::EaseOut),
+            3 => Some(Easing::EaseInOut),
+            _ => None,
+        }
+    }
+
+    /// The part of the path done at the part of the time `t`, both 0 to 1
+    pub fn apply(self, t: f64) -> f64 {
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t,
+            Easing::EaseOut => 1.0 - (1.0 - t) * (1.0 - t),
+            Easing::EaseInOut if t < 0.5 => 4.0 * t * t * t,
+            Easing::EaseInOut => 1.0 - (2.0 - 2.0 * t).powi(3) / 2.0,
+        }
+    }
+}
+
+/// Shape of a path
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathShape {
+    /// a straight line
+    Line = 0,
+    /// a cubic Bezier curve bowing to a random side
+    Bezier = 1,
+    /// WindMouse, pulled to the target with random wind, wobbling a bit
+    WindMouse = 2,
+}
+
+impl PathShape {
+    pub fn from_i32(i: i32) -> Option<PathShape> {
+        match i {
+            0 => Some(PathShape::Line),
+            1 => Some(PathShape::Bezier),
+            2 => Some(PathShape::WindMouse),
+            _ => None,
+        }
+    }
+}
+
+/// How `move_to_with` moves the cursor
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MovePath {
+    pub shape: PathShape,
+    pub easing: Easing,
+    /// time from the first point to the last one
+    pub duration: Duration,
+    /// points per second
+    pub rate: u32,
+    /// seed of the random parts, None for a new one each time
+    pub seed: Option<u64>,
+}
+
+impl Default for MovePath {
+    /// a Bezier curve in 300 milliseconds, at 120 points per second
+    fn default() -> MovePath {
+        MovePath {
+            shape: PathShape::Bezier,
+            easing: Easing::EaseInOut,
+            duration: Duration::from_millis(300),
+            rate: 120,
+            seed: None,
+        }
+    }
+}
+
+// most points of a path, more only make the moves slower than `duration`
+const MAX_STEPS: u32 = 10_000;
+
+impl MovePath {
+    /// Number of points, one per step of `1 / rate` seconds, from 1 to 10000
+    pub fn steps(&self) -> usize {
+        self.step_count() as usize
+    }
+
+    fn step_count(&self) -> u32 {
+        let steps = (self.duration.as_secs_f64() * self.rate as f64).ceil();
+        steps.clamp(1.0, MAX_STEPS as f64) as u32
+    }
+
+    /// Time between two points
+    pub fn interval(&self) -> Duration {
+        self.duration / self.step_count()
+    }
+}
+
+type Point = (f64, f64);
+
+fn lerp(a: Point, b: Point, t: f64) -> Point {
+    (a.0 + (b.0 - a.0) * t, a.1 + (b.1 - a.1) * t)
+}
+
+fn distance(a: Point, b: Point) -> f64 {
+    (b.0 - a.0).hypot(b.1 - a.1)
+}
+
+// control points at about a third and two thirds of the way, pushed to the
+// same side by up to a third of the distance
+fn bezier_controls(from: Point, to: Point, rng: &mut Rng) -> (Point, Point) {
+    let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+    let bow = rng.next_signed() / 3.0;
+    let control = |along: f64, rng: &mut Rng| {
+        let (x, y) = lerp(from, to, along + rng.next_signed() * 0.1);
+        let side = bow * (0.7 + rng.next_f64() * 0.6);
+        // perpendicular to the line
+        (x - dy * side, y + dx * side)
+    };
+    let p1 = control(1.0 / 3.0, rng);
+    let p2 = control(2.0 / 3.0, rng);
+    (p1, p2)
+}
+
+fn bezier(p0: Point, p1: Point, p2: Point, p3: Point, t: f64) -> Point {
+    let u = 1.0 - t;
+    let (a, b, c, d) = (u * u * u, 3.0 * u * u * t, 3.0 * u * t * t, t * t * t);
+    (
+        a * p0.0 + b * p1.0 + c * p2.0 + d * p3.0,
+        a * p0.1 + b * p1.1 + c * p2.1 + d * p3.1,
+    )
+}
+
+// the WindMouse polyline from `from` to `to`, gravity 9, wind 3, max step
+// 15 and wind damped within 12 pixels of the target
+fn wind_mouse(from: Point, to: Point, rng: &mut Rng) -> Vec<Point> {
+    const GRAVITY: f64 = 9.0;
+    const WIND: f64 = 3.0;
+    const NEAR: f64 = 12.0;
+    let (sqrt3, sqrt5) = (3f64.sqrt(), 5f64.sqrt());
+    let mut max_step = 15.0;
+    let (mut velocity, mut wind) = ((0.0, 0.0), (0.0, 0.0));
+    let mut pos = from;
+    let mut points = vec![from];
+    // bounded, the target is always reached
+    for _ in 0..10_000 {
+        let dist = distance(pos, to);
+        if dist < 1.0 {
+            break;
+        }
+        let wind_mag = WIND.min(dist);
+        if dist >= NEAR {
+            wind.0 = wind.0 / sqrt3 + rng.next_signed() * wind_mag / sqrt5;
+            wind.1 = wind.1 / sqrt3 + rng.next_signed() * wind_mag / sqrt5;
+        } else {
+            wind.0 /= sqrt3;
+            wind.1 /= sqrt3;
+            if max_step < 3.0 {
+                max_step = rng.next_f64() * 3.0 + 3.0;
+            } else {
+                max_step /= sqrt5;
+            }
+        }
+        velocity.0 += wind.0 + GRAVITY * (to.0 - pos.0) / dist;
+        velocity.1 += wind.1 + GRAVITY * (to.1 - pos.1) / dist;
+        let speed = velocity.0.hypot(velocity.1);
+        if speed > max_step {
+            let clipped = max_step / 2.0 + rng.next_f64() * max_step / 2.0;
+            velocity = (velocity.0 / speed * clipped, velocity.1 / speed * clipped);
+        }
+        pos = (pos.0 + velocity.0, pos.1 + velocity.1);
+        points.push(pos);
+    }
+    points.push(to);
+    points
+}
+
+// the point at the part `t` of the length of a polyline
+fn along(points: &[Point], t: f64) -> Point {
+    let total: f64 = points.windows(2).map(|w| distance(w[0], w[1])).sum();
+    let mut left = total * t;
+    for w in points.windows(2) {
+        let len = distance(w[0], w[1]);
+        if left <= len && len > 0.0 {
+            return lerp(w[0], w[1], left / len);
+        }
+        left -= len;
+    }
+    *points.last().unwrap()
+}
+
+/// The points of a path from `from` to `to`, one per step of `path`, the
+/// last one is `to`. The cursor is at `from` already, so it is left out.
+pub fn path_points(from: (i32, i32), to: (i32, i32), path: &MovePath) -> Vec<(i32, i32)> {
+    if from == to {
+        return vec![to];
+    }
+    let mut rng = match path.seed {
+        Some(seed) => Rng::new(seed),
+        None => Rng::from_time(),
+    };
+    let (start, end) = ((from.0 as f64, from.1 as f64), (to.0 as f64, to.1 as f64));
+    let shape: Box<dyn Fn(f64) -> Point> = match path.shape {
+        PathShape::Line => Box::new(move |t| lerp(start, end, t)),
+        PathShape::Bezier => {
+            let (p1, p2) = bezier_controls(start, end, &mut rng);
+            Box::new(move |t| bezier(start, p1, p2, end, t))
+        }
+        PathShape::WindMouse => {
+            let points = wind_mouse(start, end, &mut rng);
+            Box::new(move |t| along(&points, t))
+        }
+    };
+
+    let steps = path.steps();
+    let mut points: Vec<(i32, i32)> = (1..steps)
+        .map(|i| {
+            let (x, y) = shape(path.easing.apply(i as f64 / steps as f64));
+            (x.round() as i32, y.round() as i32)
+        })
+        .collect();
+    points.push(to);
+    points
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn easing() {
+        for easing in [
+            Easing::Linear,
+            Easing::EaseIn,
+            Easing::EaseOut,
+            Easing::EaseInOut,
+        ] {
+            assert_eq!(0.0, easing.apply(0.0));
+            assert_eq!(1.0, easing.apply(1.0));
+            let mut last = 0.0;
+            for i in 1..=20 {
+                let t = easing.apply(i as f64 / 20.0);
+                assert!(t >= last, "{:?} goes back", easing);
+                last = t;
+            }
+        }
+        assert!(Easing::EaseIn.apply(0.5) < 0.5);
+        assert!(Easing::EaseOut.apply(0.5) > 0.5);
+        assert_eq!(0.5, Easing::EaseInOut.apply(0.5));
+    }
+
+    #[test]
+    fn line() {
+        let path = MovePath {
+            shape: PathShape::Line,
+            easing: Easing::Linear,
+            duration: Duration::from_millis(40),
+            rate: 100,
+            seed: Some(1),
+        };
+        assert_eq!(4, path.steps());
+        assert_eq!(Duration::from_millis(10), path.interval());
+        assert_eq!(
+            vec![(25, 10), (50, 20), (75, 30), (100, 40)],
+            path_points((0, 0), (100, 40), &path)
+        );
+        assert_eq!(vec![(5, 5)], path_points((5, 5), (5, 5), &path));
+
+        let long = MovePath {
+            duration: Duration::MAX,
+            rate: u32::MAX,
+            ..path
+        };
+        assert_eq!(MAX_STEPS as usize, long.steps());
+        assert_eq!(Duration::MAX / MAX_STEPS, long.interval());
+    }
+
+    #[test]
+    fn curves_reach_the_target() {
+        for shape in [PathShape::Bezier, PathShape::WindMouse] {
+            let path = MovePath {
+                shape,
+                seed: Some(42),
+                ..MovePath::default()
+            };
+            let points = path_points((10, 500), (800, 120), &path);
+            assert_eq!(path.steps(), points.len());
+            assert_eq!(Some(&(800, 120)), points.last());
+            // not a straight line
+            let off_line = points.iter().any(|&(x, y)| {
+                let cross = (800 - 10) * (y - 500) - (120 - 500) * (x - 10);
+                cross.abs() > 5 * 877
+            });
+            assert!(off_line, "{:?} is straight", shape);
+            // steps stay small, no jump across the screen
+            let mut last = (10, 500);
+            for &p in &points {
+                assert!(distance((last.0 as f64, last.1 as f64), (p.0 as f64, p.1 as f64)) < 100.0);
+                last = p;
+            }
+            assert_eq!(points, path_points((10, 500), (800, 120), &path));
+        }
+    }
+}
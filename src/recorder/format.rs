@@ -0,0 +1,547 @@
+use std::{fs, path::Path, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+use super::{Macro, MAX_REPEAT};
+use crate::{
+    backend::Input,
+    errors::Result,
+    key::Key,
+    keyboard::{self, KeyInput},
+    mouse::{self, MouseButton, MouseInput, PressType},
+};
+
+// slowest play, a thousand times slower than recorded
+const MIN_SPEED: f64 = 0.001;
+
+// names of mouse buttons and presses in files
+const BUTTONS: [(&str, MouseButton); 5] = [
+    ("left", MouseButton::ButtonLeft),
+    ("right", MouseButton::ButtonRight),
+    ("middle", MouseButton::ButtonMiddle),
+    ("x1", MouseButton::ButtonX1),
+    ("x2", MouseButton::ButtonX2),
+];
+const PRESSES: [(&str, PressType); 3] = [
+    ("down", PressType::PressDown),
+    ("up", PressType::PressUp),
+    ("click", PressType::Click),
+];
+
+fn key_from_name(name: &str) -> Result<Key> {
+    match Key::from_name(name) {
+        Some(key) => Ok(key),
+        None => name.parse(),
+    }
+}
+
+fn button_from_name(name: &str) -> Result<MouseButton> {
+    match BUTTONS.iter().find(|(n, _)| *n == name) {
+        Some(&(_, button)) => Ok(button),
+        None => bail!("unknown mouse button {}", name),
+    }
+}
+
+fn button_name(button: MouseButton) -> &'static str {
+    BUTTONS.iter().find(|(_, b)| *b == button).unwrap().0
+}
+
+fn press_from_name(name: &str) -> Result<PressType> {
+    match PRESSES.iter().find(|(n, _)| *n == name) {
+        Some(&(_, press)) => Ok(press),
+        None => bail!("unknown press {}, expected down, up or click", name),
+    }
+}
+
+fn press_name(press: PressType) -> &'static str {
+    PRESSES.iter().find(|(_, p)| *p == press).unwrap().0
+}
+
+// serde of the fields above by name, like "enter" or "left"
+macro_rules! by_name {
+    ($module:ident, $ty:ty, $to_name:expr, $from_name:expr) => {
+        mod $module {
+            use super::*;
+
+            pub fn serialize<S: serde::Serializer>(
+                value: &$ty,
+                serializer: S,
+            ) -> std::result::Result<S::Ok, S::Error> {
+                serializer.serialize_str(&$to_name(*value))
+            }
+
+            pub fn deserialize<'de, D: serde::Deserializer<'de>>(
+                deserializer: D,
+            ) -> std::result::Result<$ty, D::Error> {
+                let name = String::deserialize(deserializer)?;
+                $from_name(&name).map_err(serde::de::Error::custom)
+            }
+        }
+    };
+}
+
+by_name!(key_name, Key, |key: Key| key.to_string(), key_from_name);
+by_name!(button, MouseButton, button_name, button_from_name);
+by_name!(press, PressType, press_name, press_from_name);
+
+/// One step of a macro file, an input or a wait before the next step
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "step", rename_all = "snake_case")]
+pub enum Step {
+    KeyDown {
+        #[serde(with = "key_name")]
+        key: Key,
+    },
+    KeyUp {
+        #[serde(with = "key_name")]
+        key: Key,
+    },
+    Char {
+        char: char,
+    },
+    ScancodeDown {
+        code: u16,
+    },
+    ScancodeUp {
+        code: u16,
+    },
+    MoveTo {
+        x: i32,
+        y: i32,
+    },
+    MoveBy {
+        x: i32,
+        y: i32,
+    },
+    Mouse {
+        #[serde(with = "button")]
+        button: MouseButton,
+        #[serde(with = "press")]
+        press: PressType,
+    },
+    Wheel {
+        amount: i32,
+    },
+    Hwheel {
+        amount: i32,
+    },
+    Wait {
+        ms: u64,
+    },
+}
+
+impl Step {
+    // the step of an input, None for a delay
+    fn of_input(input: &Input) -> Option<Step> {
+        Some(match *input {
+            Input::Key(KeyInput::KeyDown(key)) => Step::KeyDown { key },
+            Input::Key(KeyInput::KeyUp(key)) => Step::KeyUp { key },
+            Input::Key(KeyInput::Unicode(char)) => Step::Char { char },
+            Input::Key(KeyInput::ScanCodeDown(code)) => Step::ScancodeDown { code },
+            Input::Key(KeyInput::ScanCodeUp(code)) => Step::ScancodeUp { code },
+            Input::Key(KeyInput::Delay(_)) => return None,
+            Input::Mouse(MouseInput::MoveTo(x, y)) => Step::MoveTo { x, y },
+            Input::Mouse(MouseInput::MoveDelta(x, y)) => Step::MoveBy { x, y },
+            Input::Mouse(MouseInput::Press(button, press)) => Step::Mouse { button, press },
+            Input::Mouse(MouseInput::Wheel(amount)) => Step::Wheel { amount },
+            Input::Mouse(MouseInput::HWheel(amount)) => Step::Hwheel { amount },
+        })
+    }
+
+    fn input(&self) -> Input {
+        match *self {
+            Step::KeyDown { key } => Input::Key(KeyInput::KeyDown(key)),
+            Step::KeyUp { key } => Input::Key(KeyInput::KeyUp(key)),
+            Step::Char { char } => Input::Key(KeyInput::Unicode(char)),
+            Step::ScancodeDown { code } => Input::Key(KeyInput::ScanCodeDown(code)),
+            Step::ScancodeUp { code } => Input::Key(KeyInput::ScanCodeUp(code)),
+            Step::MoveTo { x, y } => Input::Mouse(MouseInput::MoveTo(x, y)),
+            Step::MoveBy { x, y } => Input::Mouse(MouseInput::MoveDelta(x, y)),
+            Step::Mouse { button, press } => Input::Mouse(MouseInput::Press(button, press)),
+            Step::Wheel { amount } => Input::Mouse(MouseInput::Wheel(amount)),
+            Step::Hwheel { amount } => Input::Mouse(MouseInput::HWheel(amount)),
+            Step::Wait { ms } => Input::Key(KeyInput::Delay(Duration::from_millis(ms))),
+        }
+    }
+
+    // a line of the text form, like `key_down enter` or `wait 35`
+    fn to_line(self) -> String {
+        match self {
+            Step::KeyDown { key } => format!("key_down {}", key),
+            Step::KeyUp { key } => format!("key_up {}", key),
+            // spaces and line breaks would not survive editors
+            Step::Char { char } if char.is_whitespace() || char.is_control() => {
+                format!("char u+{:04x}", char as u32)
+            }
+            Step::Char { char } => format!("char {}", char),
+            Step::ScancodeDown { code } => format!("scancode_down {:#x}", code),
+            Step::ScancodeUp { code } => format!("scancode_up {:#x}", code),
+            Step::MoveTo { x, y } => format!("move_to {} {}", x, y),
+            Step::MoveBy { x, y } => format!("move_by {} {}", x, y),
+            Step::Mouse { button, press } => {
+                format!("mouse {} {}", button_name(button), press_name(press))
+            }
+            Step::Wheel { amount } => format!("wheel {}", amount),
+            Step::Hwheel { amount } => format!("hwheel {}", amount),
+            Step::Wait { ms } => format!("wait {}", ms),
+        }
+    }
+
+    fn from_line(line: &str) -> Result<Step> {
+        let (name, rest) = line.split_once(' ').unwrap_or((line, ""));
+        // like `char é` or `char u+000a`
+        if name == "char" {
+            let rest = rest.trim();
+            let mut chars = rest.chars();
+            if let (Some(char), None) = (chars.next(), chars.next()) {
+                return Ok(Step::Char { char });
+            }
+            let code = rest
+                .strip_prefix("u+")
+                .or_else(|| rest.strip_prefix("U+"))
+                .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                .and_then(char::from_u32);
+            return match code {
+                Some(char) => Ok(Step::Char { char }),
+                None => bail!("expected a single char or u+ code point, got {:?}", rest),
+            };
+        }
+        let args: Vec<&str> = rest.split_whitespace().collect();
+        let (first, second) = match args[..] {
+            [] => ("", None),
+            [first] => (first, None),
+            [first, second] => (first, Some(second)),
+            _ => bail!("too many arguments"),
+        };
+        let number = |s: &str| -> Result<i32> {
+            s.parse()
+                .map_err(|_| format!("expected a number, got {:?}", s).into())
+        };
+        let scancode = |s: &str| -> Result<u16> {
+            let code = match s.strip_prefix("0x") {
+                Some(hex) => u16::from_str_radix(hex, 16).ok(),
+                None => s.parse().ok(),
+            };
+            code.ok_or_else(|| format!("expected a scan code like 0x1e, got {:?}", s).into())
+        };
+        let step = match (name, second) {
+            ("key_down", None) => Step::KeyDown {
+                key: key_from_name(first)?,
+            },
+            ("key_up", None) => Step::KeyUp {
+                key: key_from_name(first)?,
+            },
+            ("scancode_down", None) => Step::ScancodeDown {
+                code: scancode(first)?,
+            },
+            ("scancode_up", None) => Step::ScancodeUp {
+                code: scancode(first)?,
+            },
+            ("move_to", Some(y)) => Step::MoveTo {
+                x: number(first)?,
+                y: number(y)?,
+            },
+            ("move_by", Some(y)) => Step::MoveBy {
+                x: number(first)?,
+                y: number(y)?,
+            },
+            ("mouse", Some(press)) => Step::Mouse {
+                button: button_from_name(first)?,
+                press: press_from_name(press)?,
+            },
+            ("wheel", None) => Step::Wheel {
+                amount: number(first)?,
+            },
+            ("hwheel", None) => Step::Hwheel {
+                amount: number(first)?,
+            },
+            ("wait", None) => match first.parse() {
+                Ok(ms) => Step::Wait { ms },
+                Err(_) => bail!("expected milliseconds, got {:?}", first),
+            },
+            _ => bail!("unknown step {:?}", line),
+        };
+        Ok(step)
+    }
+}
+
+// on disk, JSON
+#[derive(Serialize, Deserialize)]
+struct MacroFile {
+    steps: Vec<Step>,
+}
+
+impl Macro {
+    /// The macro of `steps`, the inputs at the sum of the waits before them
+    pub fn from_steps(steps: &[Step]) -> Macro {
+        let mut offset = Duration::ZERO;
+        let mut events = Vec::new();
+        for step in steps {
+            match step {
+                Step::Wait { ms } => offset += Duration::from_millis(*ms),
+                _ => events.push((offset, step.input())),
+            }
+        }
+        Macro { events }
+    }
+
+    /// The steps of the macro, offsets are rounded to milliseconds
+    pub fn steps(&self) -> Vec<Step> {
+        let mut steps = Vec::new();
+        let mut last_ms = 0;
+        for (at, input) in &self.events {
+            let ms = (at.as_secs_f64() * 1000.0).round() as u64;
+            if ms > last_ms {
+                steps.push(Step::Wait { ms: ms - last_ms });
+                last_ms = ms;
+            }
+            if let Some(step) = Step::of_input(input) {
+                steps.push(step);
+            }
+        }
+        steps
+    }
+
+    /// JSON form, like `{"steps":[{"step":"key_down","key":"enter"}]}`
+    pub fn to_json(&self) -> Result<String> {
+        let file = MacroFile {
+            steps: self.steps(),
+        };
+        Ok(serde_json::to_string_pretty(&file)?)
+    }
+
+    pub fn from_json(s: &str) -> Result<Macro> {
+        let file: MacroFile = serde_json::from_str(s)?;
+        Ok(Macro::from_steps(&file.steps))
+    }
+
+    /// Text form, a step per line, like `key_down enter`, `mouse left click`
+    /// or `wait 35`
+    pub fn to_text(&self) -> String {
+        self.steps()
+            .into_iter()
+            .map(|step| step.to_line() + "\n")
+            .collect()
+    }
+
+    /// Parse the text form, empty lines and lines starting with `#` are skipped
+    pub fn from_text(s: &str) -> Result<Macro> {
+        let mut steps = Vec::new();
+        for (i, line) in s.lines().enumerate() {
+            let line = line.trim_end_matches('\r');
+            if line.trim().is_empty() || line.trim_start().starts_with('#') {
+                continue;
+            }
+            match Step::from_line(line.trim_start()) {
+                Ok(step) => steps.push(step),
+                Err(e) => bail!("line {}: {}", i + 1, e),
+            }
+        }
+        Ok(Macro::from_steps(&steps))
+    }
+
+    /// Load a macro saved by `save`, JSON when it starts with `{`, text otherwise
+    pub fn load(path: impl AsRef<Path>) -> Result<Macro> {
+        let s = fs::read_to_string(path)?;
+        if s.trim_start().starts_with('{') {
+            Macro::from_json(&s)
+        } else {
+            Macro::from_text(&s)
+        }
+    }
+
+    /// Save the macro, as JSON when `path` ends with `.json`, as text otherwise
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let s = match path.extension() {
+            Some(ext) if ext.eq_ignore_ascii_case("json") => self.to_json()?,
+            _ => self.to_text(),
+        };
+        fs::write(path, s)?;
+        Ok(())
+    }
+
+    // the inputs of one play with the waits between them at `speed`
+    fn play_inputs(&self, speed: f64) -> Result<Vec<Input>> {
+        let mut inputs = Vec::new();
+        let mut last = Duration::ZERO;
+        for (at, input) in &self.events {
+            if *at > last {
+                // too long for a `Duration` when the speed is tiny
+                let wait = match Duration::try_from_secs_f64((*at - last).as_secs_f64() / speed) {
+                    Ok(wait) => wait,
+                    Err(_) => bail!("invalid speed {}, waits are too long", speed),
+                };
+                inputs.push(Input::Key(KeyInput::Delay(wait)));
+                last = *at;
+            }
+            inputs.push(*input);
+        }
+        Ok(inputs)
+    }
+
+    /// Play the macro `repeat` times, up to `MAX_REPEAT`, `speed` times as fast
+    /// as recorded, 0.001 or above
+    pub fn play(&self, speed: f64, repeat: u32) -> Result<()> {
+        if !(speed.is_finite() && speed >= MIN_SPEED) {
+            bail!("invalid speed {}, expected {} or above", speed, MIN_SPEED);
+        }
+        if repeat > MAX_REPEAT {
+            bail!("invalid repeat {}, expected up to {}", repeat, MAX_REPEAT);
+        }
+        let inputs = self.play_inputs(speed)?;
+        for _ in 0..repeat {
+            play_once(&inputs)?;
+        }
+        Ok(())
+    }
+}
+
+// send the key and mouse inputs in order, the runs of each kind as one batch
+fn play_once(inputs: &[Input]) -> Result<()> {
+    let mut keys: Vec<KeyInput> = Vec::new();
+    let mut mouses: Vec<MouseInput> = Vec::new();
+    for input in inputs {
+        match *input {
+            Input::Key(k) => {
+                if !mouses.is_empty() {
+                    mouse::input_mouses(&std::mem::take(&mut mouses))?;
+                }
+                keys.push(k);
+            }
+            Input::Mouse(m) => {
+                if !keys.is_empty() {
+                    keyboard::input_keys(&std::mem::take(&mut keys))?;
+                }
+                mouses.push(m);
+            }
+        }
+    }
+    if !keys.is_empty() {
+        keyboard::input_keys(&keys)?;
+    }
+    if !mouses.is_empty() {
+        mouse::input_mouses(&mouses)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::backend::{with_backend, RecordingBackend};
+
+    fn sample() -> Macro {
+        let ms = Duration::from_millis;
+        Macro {
+            events: vec![
+                (ms(0), Input::Key(KeyInput::KeyDown(Key::LCtrl))),
+                (ms(35), Input::Key(KeyInput::KeyDown(Key::C))),
+                (ms(35), Input::Key(KeyInput::KeyUp(Key::C))),
+                (ms(50), Input::Key(KeyInput::KeyUp(Key::LCtrl))),
+                (ms(50), Input::Key(KeyInput::Unicode(' '))),
+                (ms(50), Input::Key(KeyInput::Unicode('\n'))),
+                (ms(50), Input::Key(KeyInput::Unicode('\t'))),
+                (ms(50), Input::Key(KeyInput::Unicode('é'))),
+                (ms(60), Input::Key(KeyInput::ScanCodeDown(0xE048))),
+                (ms(80), Input::Mouse(MouseInput::MoveTo(300, -4))),
+                (ms(80), Input::Mouse(MouseInput::MoveDelta(-10, 5))),
+                (
+                    ms(120),
+                    Input::Mouse(MouseInput::Press(MouseButton::ButtonX1, PressType::Click)),
+                ),
+                (ms(130), Input::Mouse(MouseInput::Wheel(-120))),
+                (ms(130), Input::Mouse(MouseInput::HWheel(120))),
+            ],
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let m = sample();
+        assert_eq!(m, Macro::from_json(&m.to_json().unwrap()).unwrap());
+        assert_eq!(m, Macro::from_text(&m.to_text()).unwrap());
+
+        let text = m.to_text();
+        assert!(text.starts_with("key_down lctrl\nwait 35\nkey_down C\n"));
+        assert!(text.contains("\nchar u+0020\nchar u+000a\nchar u+0009\nchar é\nwait 10\n"));
+        // trailing spaces trimmed by an editor
+        let trimmed: String = text
+            .lines()
+            .map(|line| line.trim_end().to_string() + "\n")
+            .collect();
+        assert_eq!(m, Macro::from_text(&trimmed).unwrap());
+        assert!(text.contains("\nmouse x1 click\n"));
+
+        let json = m.to_json().unwrap();
+        assert!(json.contains(r#""step": "mouse""#));
+        assert!(json.contains(r#""button": "x1""#));
+    }
+
+    #[test]
+    fn hand_written() {
+        let m = Macro::from_text(
+            "# copy\nkey_down ctrl\n  key_down C\nwait 20\n\nkey_up C\r\nkey_up ctrl\n",
+        )
+        .unwrap();
+        assert_eq!(
+            m.steps(),
+            vec![
+                Step::KeyDown { key: Key::Ctrl },
+                Step::KeyDown { key: Key::C },
+                Step::Wait { ms: 20 },
+                Step::KeyUp { key: Key::C },
+                Step::KeyUp { key: Key::Ctrl },
+            ]
+        );
+        let json =
+            r#"{"steps": [{"step": "wait", "ms": 5}, {"step": "key_down", "key": "enter"}]}"#;
+        assert_eq!(
+            Macro::from_json(json).unwrap().events,
+            vec![(
+                Duration::from_millis(5),
+                Input::Key(KeyInput::KeyDown(Key::Enter))
+            )]
+        );
+
+        let err = |s: &str| Macro::from_text(s).unwrap_err().to_string();
+        assert_eq!("line 2: unknown step \"jump 3\"", err("wait 1\njump 3"));
+        assert!(err("mouse left hold").contains("down, up or click"));
+        assert!(err("key_down nokey").starts_with("line 1:"));
+        assert!(Macro::from_json(r#"{"steps": [{"step": "mouse", "button": "side"}]}"#).is_err());
+    }
+
+    #[test]
+    fn play() {
+        let m = sample();
+        let ms = Duration::from_millis;
+        let inputs = m.play_inputs(2.0).unwrap();
+        let delays: Vec<Duration> = inputs
+            .iter()
+            .filter_map(|input| match input {
+                Input::Key(KeyInput::Delay(d)) => Some(*d),
+                _ => None,
+            })
+            .collect();
+        let run = [ms(35) / 2, ms(15) / 2, ms(5), ms(10), ms(20), ms(5)];
+        assert_eq!(delays, run);
+        assert_eq!(m.events.len() + delays.len(), inputs.len());
+
+        let rec = Arc::new(RecordingBackend::new());
+        let fast = Macro {
+            events: m.events.iter().map(|(_, input)| (ms(0), *input)).collect(),
+        };
+        with_backend(rec.clone(), || fast.play(1.0, 2)).unwrap();
+        let events: Vec<Input> = m.events.iter().map(|(_, input)| *input).collect();
+        assert_eq!([&events[..], &events[..]].concat(), rec.inputs());
+        assert!(m.play(0.0, 1).is_err());
+        assert!(m.play(1e-20, 1).is_err());
+        let long = Macro {
+            events: vec![(Duration::MAX, m.events[0].1)],
+        };
+        assert!(long.play_inputs(MIN_SPEED).is_err());
+        assert!(fast.play(1.0, MAX_REPEAT + 1).is_err());
+    }
+}
@@ -0,0 +1,213 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use crate::{backend::Input, errors::Result};
+
+mod format;
+mod raw;
+#[cfg(windows)]
+mod win32;
+
+pub use format::Step;
+pub use raw::{Decoder, RawKeyboard, RawMouse};
+
+/// Most times a macro can be played in one call
+pub const MAX_REPEAT: u32 = 10_000;
+
+/// Recorded inputs at their offsets from the start of the recording
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Macro {
+    pub events: Vec<(Duration, Input)>,
+}
+
+impl Macro {
+    /// Offset of the last input
+    pub fn duration(&self) -> Duration {
+        self.events.last().map(|(at, _)| *at).unwrap_or_default()
+    }
+}
+
+/// Builds a macro from raw records
+#[derive(Debug)]
+pub struct Recorder {
+    start: Instant,
+    decoder: Decoder,
+    events: Vec<(Duration, Input)>,
+}
+
+impl Recorder {
+    /// A recorder of records from `start`
+    pub fn new(start: Instant) -> Recorder {
+        Recorder {
+            start,
+            decoder: Decoder::new(),
+            events: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, at: Instant, input: Input) {
+        let offset = at.saturating_duration_since(self.start);
+        self.events.push((offset, input));
+    }
+
+    /// Record a keyboard record received at `at`
+    pub fn key(&mut self, at: Instant, raw: &RawKeyboard) {
+        if let Some(input) = self.decoder.key(raw) {
+            self.push(at, Input::Key(input));
+        }
+    }
+
+    /// Record a mouse record received at `at`, with the cursor at `cursor`
+    pub fn mouse(&mut self, at: Instant, raw: &RawMouse, cursor: (i32, i32)) {
+        for input in self.decoder.mouse(raw, cursor) {
+            self.push(at, Input::Mouse(input));
+        }
+    }
+
+    pub fn finish(self) -> Macro {
+        Macro {
+            events: self.events,
+        }
+    }
+}
+
+lazy_static! {
+    static ref RECORDER: Mutex<Option<Recorder>> = Mutex::new(None);
+    static ref KEPT: Mutex<(i32, HashMap<i32, Macro>)> = Mutex::new((0, HashMap::new()));
+}
+
+// pass a record of the hook to the recorder, if still recording
+#[cfg(windows)]
+fn with_recorder(f: impl FnOnce(&mut Recorder)) {
+    if let Some(recorder) = RECORDER.lock().unwrap().as_mut() {
+        f(recorder);
+    }
+}
+
+#[cfg(windows)]
+use win32::{start_hook, stop_hook};
+
+#[cfg(not(windows))]
+fn start_hook() -> Result<()> {
+    bail!("recording is only available on windows")
+}
+
+#[cfg(not(windows))]
+fn stop_hook() -> Result<()> {
+    Ok(())
+}
+
+/// Start recording the keyboard and mouse, on a thread of its own
+pub fn start() -> Result<()> {
+    let mut recorder = RECORDER.lock().unwrap();
+    if recorder.is_some() {
+        bail!("already recording");
+    }
+    // records come in once the hook is started
+    *recorder = Some(Recorder::new(Instant::now()));
+    drop(recorder);
+    if let Err(e) = start_hook() {
+        *RECORDER.lock().unwrap() = None;
+        return Err(e);
+    }
+    Ok(())
+}
+
+/// Stop recording, return what was recorded since `start`
+pub fn stop() -> Result<Macro> {
+    stop_hook()?;
+    match RECORDER.lock().unwrap().take() {
+        Some(recorder) => Ok(recorder.finish()),
+        None => bail!("not recording"),
+    }
+}
+
+/// Keep `m` for callers without a `Macro` of their own, like C, return its id
+pub fn keep(m: Macro) -> i32 {
+    let mut kept = KEPT.lock().unwrap();
+    kept.0 += 1;
+    let id = kept.0;
+    kept.1.insert(id, m);
+    id
+}
+
+/// The macro kept as `id`
+pub fn kept(id: i32) -> Result<Macro> {
+    match KEPT.lock().unwrap().1.get(&id) {
+        Some(m) => Ok(m.clone()),
+        None => bail!("no macro {}", id),
+    }
+}
+
+/// Drop the macro kept as `id`, return whether it was kept
+pub fn forget(id: i32) -> bool {
+    KEPT.lock().unwrap().1.remove(&id).is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        key::Key,
+        keyboard::KeyInput,
+        mouse::{MouseButton, MouseInput, PressType},
+    };
+
+    #[test]
+    fn record() {
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+        let mut recorder = Recorder::new(start);
+        let key = |make_code, flags| RawKeyboard {
+            make_code,
+            flags,
+            vkey: 0x43,
+        };
+        recorder.mouse(
+            at(5),
+            &RawMouse {
+                last_x: 1,
+                button_flags: 0x001,
+                ..RawMouse::default()
+            },
+            (10, 20),
+        );
+        recorder.key(at(20), &key(0x2E, 0));
+        recorder.key(at(30), &key(0x2E, 0));
+        recorder.key(at(55), &key(0x2E, 1));
+
+        let ms = Duration::from_millis;
+        let m = recorder.finish();
+        assert_eq!(
+            m.events,
+            vec![
+                (ms(5), Input::Mouse(MouseInput::MoveTo(10, 20))),
+                (
+                    ms(5),
+                    Input::Mouse(MouseInput::Press(
+                        MouseButton::ButtonLeft,
+                        PressType::PressDown
+                    ))
+                ),
+                (ms(20), Input::Key(KeyInput::KeyDown(Key::C))),
+                (ms(55), Input::Key(KeyInput::KeyUp(Key::C))),
+            ]
+        );
+        assert_eq!(ms(55), m.duration());
+
+        let id = keep(m.clone());
+        assert_eq!(m, kept(id).unwrap());
+        assert!(forget(id));
+        assert!(kept(id).is_err());
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn start_and_stop() {
+        assert!(start().is_err());
+        assert!(stop().is_err());
+    }
+}
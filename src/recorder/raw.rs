@@ -0,0 +1,213 @@
+use crate::{
+    key::Key,
+    keyboard::KeyInput,
+    mouse::{MouseButton, MouseInput, PressType},
+};
+
+// RAWKEYBOARD flags
+const RI_KEY_BREAK: u16 = 1;
+const RI_KEY_E0: u16 = 2;
+// keys without a key of their own, like the first half of pause
+const VK_FAKE: u16 = 0xFF;
+const VK_SHIFT: u16 = 0x10;
+const VK_MENU: u16 = 0x12;
+
+// RAWMOUSE flags
+const MOUSE_MOVE_ABSOLUTE: u16 = 1;
+const RI_MOUSE_WHEEL: u16 = 0x400;
+const RI_MOUSE_HWHEEL: u16 = 0x800;
+
+// RAWMOUSE button flags, in the order they are decoded
+const BUTTON_FLAGS: [(u16, MouseButton, PressType); 10] = [
+    (0x001, MouseButton::ButtonLeft, PressType::PressDown),
+    (0x002, MouseButton::ButtonLeft, PressType::PressUp),
+    (0x004, MouseButton::ButtonRight, PressType::PressDown),
+    (0x008, MouseButton::ButtonRight, PressType::PressUp),
+    (0x010, MouseButton::ButtonMiddle, PressType::PressDown),
+    (0x020, MouseButton::ButtonMiddle, PressType::PressUp),
+    (0x040, MouseButton::ButtonX1, PressType::PressDown),
+    (0x080, MouseButton::ButtonX1, PressType::PressUp),
+    (0x100, MouseButton::ButtonX2, PressType::PressDown),
+    (0x200, MouseButton::ButtonX2, PressType::PressUp),
+];
+
+/// A raw keyboard record, the fields of a win32 `RAWKEYBOARD`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RawKeyboard {
+    pub make_code: u16,
+    pub flags: u16,
+    pub vkey: u16,
+}
+
+/// A raw mouse record, the fields of a win32 `RAWMOUSE`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RawMouse {
+    pub flags: u16,
+    pub button_flags: u16,
+    pub button_data: u16,
+    pub last_x: i32,
+    pub last_y: i32,
+}
+
+/// Turns raw records into inputs, dropping auto repeats and releases of keys
+/// pressed before the recording
+#[derive(Debug, Default)]
+pub struct Decoder {
+    held: Vec<Key>,
+    cursor: Option<(i32, i32)>,
+}
+
+impl Decoder {
+    pub fn new() -> Decoder {
+        Decoder::default()
+    }
+
+    /// The input of a keyboard record, None when there is nothing to replay
+    pub fn key(&mut self, raw: &RawKeyboard) -> Option<KeyInput> {
+        let extended = raw.flags & RI_KEY_E0 != 0;
+        // shifts faked around extended keys while num lock is on
+        if raw.vkey == VK_FAKE || (extended && matches!(raw.make_code, 0x2A | 0x36)) {
+            return None;
+        }
+        let scancode = if extended {
+            0xE000 | raw.make_code
+        } else {
+            raw.make_code
+        };
+        // the side of shift, ctrl and alt is in the scan code only
+        let key = if (VK_SHIFT..=VK_MENU).contains(&raw.vkey) {
+            Key::from_scancode(scancode)
+        } else {
+            match raw.vkey {
+                // no virtual key, like some media keys
+                0 => Key::from_scancode(scancode),
+                vkey => Some(Key::from_vk(vkey as u8)),
+            }
+        }?;
+
+        if raw.flags & RI_KEY_BREAK != 0 {
+            let pos = self.held.iter().position(|held| *held == key)?;
+            self.held.remove(pos);
+            Some(KeyInput::KeyUp(key))
+        } else if self.held.contains(&key) {
+            None
+        } else {
+            self.held.push(key);
+            Some(KeyInput::KeyDown(key))
+        }
+    }
+
+    /// The inputs of a mouse record, with the cursor at `cursor` after it
+    pub fn mouse(&mut self, raw: &RawMouse, cursor: (i32, i32)) -> Vec<MouseInput> {
+        let mut inputs = Vec::new();
+        let moved = raw.flags & MOUSE_MOVE_ABSOLUTE != 0 || raw.last_x != 0 || raw.last_y != 0;
+        if moved && self.cursor != Some(cursor) {
+            inputs.push(MouseInput::MoveTo(cursor.0, cursor.1));
+        }
+        self.cursor = Some(cursor);
+        for (flag, button, press) in BUTTON_FLAGS {
+            if raw.button_flags & flag != 0 {
+                inputs.push(MouseInput::Press(button, press));
+            }
+        }
+        // the wheel amount is signed
+        let amount = raw.button_data as i16 as i32;
+        if raw.button_flags & RI_MOUSE_WHEEL != 0 {
+            inputs.push(MouseInput::Wheel(amount));
+        }
+        if raw.button_flags & RI_MOUSE_HWHEEL != 0 {
+            inputs.push(MouseInput::HWheel(amount));
+        }
+        inputs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn down(vkey: u16, make_code: u16, flags: u16) -> RawKeyboard {
+        RawKeyboard {
+            make_code,
+            flags,
+            vkey,
+        }
+    }
+
+    fn up(vkey: u16, make_code: u16, flags: u16) -> RawKeyboard {
+        down(vkey, make_code, flags | RI_KEY_BREAK)
+    }
+
+    #[test]
+    fn keys() {
+        let mut decoder = Decoder::new();
+        let mut decode = |raw: RawKeyboard| decoder.key(&raw);
+        // released before it was pressed
+        assert_eq!(None, decode(up(0x41, 0x1E, 0)));
+        assert_eq!(
+            Some(KeyInput::KeyDown(Key::RCtrl)),
+            decode(down(0x11, 0x1D, RI_KEY_E0))
+        );
+        assert_eq!(
+            Some(KeyInput::KeyDown(Key::RShift)),
+            decode(down(0x10, 0x36, 0))
+        );
+        assert_eq!(Some(KeyInput::KeyDown(Key::A)), decode(down(0x41, 0x1E, 0)));
+        // auto repeat
+        assert_eq!(None, decode(down(0x41, 0x1E, 0)));
+        assert_eq!(Some(KeyInput::KeyUp(Key::A)), decode(up(0x41, 0x1E, 0)));
+        // fake shift before an extended key
+        assert_eq!(None, decode(down(0x10, 0x2A, RI_KEY_E0)));
+        assert_eq!(
+            Some(KeyInput::KeyDown(Key::Left)),
+            decode(down(0x25, 0x4B, RI_KEY_E0))
+        );
+        assert_eq!(None, decode(down(VK_FAKE, 0x1D, 0)));
+        assert_eq!(
+            Some(KeyInput::KeyUp(Key::RCtrl)),
+            decode(up(0x11, 0x1D, RI_KEY_E0))
+        );
+    }
+
+    #[test]
+    fn mouse() {
+        let mut decoder = Decoder::new();
+        let moved = RawMouse {
+            last_x: 3,
+            last_y: -1,
+            ..RawMouse::default()
+        };
+        assert_eq!(
+            vec![MouseInput::MoveTo(100, 200)],
+            decoder.mouse(&moved, (100, 200))
+        );
+        // pushed against the edge of the screen
+        assert!(decoder.mouse(&moved, (100, 200)).is_empty());
+
+        let clicked = RawMouse {
+            button_flags: 0x001 | 0x008,
+            ..RawMouse::default()
+        };
+        assert_eq!(
+            vec![
+                MouseInput::Press(MouseButton::ButtonLeft, PressType::PressDown),
+                MouseInput::Press(MouseButton::ButtonRight, PressType::PressUp),
+            ],
+            decoder.mouse(&clicked, (100, 200))
+        );
+
+        let wheel = |flag, amount: i16| RawMouse {
+            button_flags: flag,
+            button_data: amount as u16,
+            ..RawMouse::default()
+        };
+        assert_eq!(
+            vec![MouseInput::Wheel(-120)],
+            decoder.mouse(&wheel(RI_MOUSE_WHEEL, -120), (100, 200))
+        );
+        assert_eq!(
+            vec![MouseInput::HWheel(240)],
+            decoder.mouse(&wheel(RI_MOUSE_HWHEEL, 240), (100, 200))
+        );
+    }
+}
@@ -0,0 +1,110 @@
+use std::{
+    sync::{mpsc, Mutex},
+    thread::{self, JoinHandle},
+    time::Instant,
+};
+
+use windows::Win32::{
+    Foundation::{HWND, LPARAM, LRESULT, POINT, WPARAM},
+    UI::{
+        Input::{RAWINPUT, RIM_TYPEKEYBOARD, RIM_TYPEMOUSE},
+        WindowsAndMessaging::{
+            DefWindowProcA, GetCursorPos, PostMessageA, PostQuitMessage, WM_CLOSE, WM_DESTROY,
+            WM_INPUT,
+        },
+    },
+};
+
+use super::{with_recorder, RawKeyboard, RawMouse};
+use crate::{
+    errors::{Error, Result},
+    window,
+};
+
+lazy_static! {
+    // the window receiving raw input and the thread running it
+    static ref HOOK: Mutex<Option<(isize, JoinHandle<()>)>> = Mutex::new(None);
+}
+
+fn record(raw: &RAWINPUT) {
+    let at = Instant::now();
+    // injected by SendInput, like a macro playing
+    if raw.header.hDevice.0 == 0 {
+        return;
+    }
+    if raw.header.dwType == RIM_TYPEKEYBOARD.0 {
+        let kb = unsafe { &raw.data.keyboard };
+        let raw = RawKeyboard {
+            make_code: kb.MakeCode,
+            flags: kb.Flags,
+            vkey: kb.VKey,
+        };
+        with_recorder(|recorder| recorder.key(at, &raw));
+    } else if raw.header.dwType == RIM_TYPEMOUSE.0 {
+        let mouse = unsafe { &raw.data.mouse };
+        let buttons = unsafe { mouse.Anonymous.Anonymous };
+        let raw = RawMouse {
+            flags: mouse.usFlags,
+            button_flags: buttons.usButtonFlags,
+            button_data: buttons.usButtonData,
+            last_x: mouse.lLastX,
+            last_y: mouse.lLastY,
+        };
+        let mut point = POINT::default();
+        unsafe { GetCursorPos(&mut point) };
+        with_recorder(|recorder| recorder.mouse(at, &raw, (point.x, point.y)));
+    }
+}
+
+extern "system" fn wnd_proc(hwnd: HWND, msg: u32, w_param: WPARAM, l_param: LPARAM) -> LRESULT {
+    match msg {
+        WM_INPUT => match window::raw_input(l_param) {
+            Ok(raw) => record(&raw),
+            Err(e) => eprintln!("{}", e),
+        },
+        WM_DESTROY => unsafe { PostQuitMessage(0) },
+        _ => {}
+    }
+    unsafe { DefWindowProcA(hwnd, msg, w_param, l_param) }
+}
+
+/// Receive raw keyboard and mouse input on a thread of its own
+pub(super) fn start_hook() -> Result<()> {
+    let (sender, receiver) = mpsc::channel();
+    let thread = thread::spawn(move || {
+        let hwnd = window::message_window("HotkeyzRecorder\0", wnd_proc).and_then(|hwnd| {
+            window::register_raw_input(hwnd, true)?;
+            Ok(hwnd)
+        });
+        let started = hwnd.is_ok();
+        let _ = sender.send(hwnd.map(|hwnd| hwnd.0));
+        if started {
+            window::message_loop();
+        }
+    });
+    let hwnd = match receiver.recv() {
+        Ok(hwnd) => hwnd?,
+        Err(_) => bail!("the recording thread stopped"),
+    };
+    *HOOK.lock().unwrap() = Some((hwnd, thread));
+    Ok(())
+}
+
+/// Close the window of the hook and wait for its thread
+pub(super) fn stop_hook() -> Result<()> {
+    let (hwnd, thread) = match HOOK.lock().unwrap().take() {
+        Some(hook) => hook,
+        None => return Ok(()),
+    };
+    let ret = unsafe { PostMessageA(HWND(hwnd), WM_CLOSE, WPARAM(0), LPARAM(0)) };
+    if !ret.as_bool() {
+        bail!(Error::with_chain(
+            std::io::Error::last_os_error(),
+            "PostMessageA() failed"
+        ));
+    }
+    if thread.join().is_err() {
+        bail!("the recording thread panicked");
+    }
+    Ok(())
+}
@@ -1,7 +1,9 @@
 use windows::{
     core::{PCSTR, PCWSTR},
     Win32::{
-        Foundation::{HWND, LPARAM, LRESULT, RECT, WPARAM},
+        Foundation::{
+            GetLastError, ERROR_CLASS_ALREADY_EXISTS, HWND, LPARAM, LRESULT, RECT, WPARAM,
+        },
         System::LibraryLoader::GetModuleHandleA,
         UI::{
             Input::{
@@ -39,7 +41,7 @@ pub fn get_window_rect(hwnd: &HWND) -> Option<Rect> {
     let mut rect: RECT = unsafe { std::mem::zeroed() };
     let ret = unsafe { GetWindowRect(hwnd, &mut rect) };
     if !ret.as_bool() {
-        return None
+        return None;
     }
     Some(Rect {
         left: rect.left,
@@ -49,46 +51,59 @@ pub fn get_window_rect(hwnd: &HWND) -> Option<Rect> {
     })
 }
 
+/// The raw input of a `WM_INPUT` message
+pub(crate) fn raw_input(l_param: LPARAM) -> Result<RAWINPUT> {
+    let mut raw: RAWINPUT = unsafe { std::mem::zeroed() };
+    let mut raw_size: u32 = std::mem::size_of::<RAWINPUT>() as u32;
+    let ret = unsafe {
+        GetRawInputData(
+            HRAWINPUT(l_param.0),
+            RID_INPUT,
+            std::mem::transmute(&mut raw),
+            &mut raw_size,
+            std::mem::size_of::<RAWINPUTHEADER>() as u32,
+        )
+    };
+    if ret as i32 == -1 {
+        bail!(Error::with_chain(
+            std::io::Error::last_os_error(),
+            "GetRawInputData() failed"
+        ));
+    }
+    Ok(raw)
+}
+
 extern "system" fn wnd_proc(hwnd: HWND, msg: u32, w_param: WPARAM, l_param: LPARAM) -> LRESULT {
     if msg == WM_INPUT {
-        let mut kri: RAWINPUT = unsafe { std::mem::zeroed() };
-        let mut kri_size: u32 = std::mem::size_of::<RAWINPUT>() as u32;
-        let ret = unsafe {
-            GetRawInputData(
-                HRAWINPUT(l_param.0),
-                RID_INPUT,
-                std::mem::transmute(&mut kri),
-                &mut kri_size,
-                std::mem::size_of::<RAWINPUTHEADER>() as u32,
-            )
-        };
-        if ret as i32 == -1 {
-            println!(
-                "GetRawInputData() failed, {:?}",
-                std::io::Error::last_os_error()
-            );
-        } else {
-            unsafe {
+        match raw_input(l_param) {
+            Err(e) => println!("{}", e),
+            Ok(kri) => unsafe {
                 let kb = &kri.data.keyboard;
                 println!(
                     "GetRawInputData() succeeded. vk:{}, flags:{}, make code:{}, message:{}",
                     kb.VKey, kb.Flags, kb.MakeCode, kb.Message
                 );
-            }
+            },
         }
     }
     unsafe { DefWindowProcA(hwnd, msg, w_param, l_param) }
 }
 
-pub fn log_key() -> Result<()> {
+/// Create a message only window of `class`, a nul terminated name, handling
+/// messages with `wnd_proc`
+pub(crate) fn message_window(
+    class: &'static str,
+    wnd_proc: extern "system" fn(HWND, u32, WPARAM, LPARAM) -> LRESULT,
+) -> Result<HWND> {
     let mut wcx: WNDCLASSEXA = unsafe { std::mem::zeroed() };
     wcx.cbSize = std::mem::size_of::<WNDCLASSEXA>() as u32;
     wcx.lpfnWndProc = Some(wnd_proc);
     wcx.hInstance = unsafe { GetModuleHandleA(PCSTR::default()).unwrap() };
-    wcx.lpszClassName = PCSTR("KeyLogger\0".as_ptr());
+    wcx.lpszClassName = PCSTR(class.as_ptr());
 
     let ret = unsafe { RegisterClassExA(&wcx) };
-    if ret == 0 {
+    // registered by an earlier window of the same class
+    if ret == 0 && unsafe { GetLastError() } != ERROR_CLASS_ALREADY_EXISTS {
         bail!(Error::with_chain(
             std::io::Error::last_os_error(),
             "RegisterClassExA() failed"
@@ -99,7 +114,7 @@ pub fn log_key() -> Result<()> {
         CreateWindowExA(
             WINDOW_EX_STYLE::default(),
             wcx.lpszClassName,
-            "KeyLogger",
+            wcx.lpszClassName,
             WINDOW_STYLE::default(),
             0,
             0,
@@ -118,15 +133,25 @@ pub fn log_key() -> Result<()> {
             "CreateWindowExA() failed"
         ));
     }
+    Ok(hwnd)
+}
 
-    let mut rids: [RAWINPUTDEVICE; 1] = unsafe { std::mem::zeroed() };
-    rids[0].usUsagePage = 1;
-    rids[0].usUsage = 6;
-    rids[0].dwFlags = RIDEV_INPUTSINK;
-    rids[0].hwndTarget = hwnd;
+/// Send the raw input of the keyboard, and of the mouse if `mouse`, to
+/// `hwnd` as `WM_INPUT`, even when it is not in the foreground
+pub(crate) fn register_raw_input(hwnd: HWND, mouse: bool) -> Result<()> {
+    let mut rids: [RAWINPUTDEVICE; 2] = unsafe { std::mem::zeroed() };
+    // generic desktop page, keyboard and mouse usages
+    for (rid, usage) in rids.iter_mut().zip([6, 2]) {
+        rid.usUsagePage = 1;
+        rid.usUsage = usage;
+        rid.dwFlags = RIDEV_INPUTSINK;
+        rid.hwndTarget = hwnd;
+    }
+    let count = if mouse { 2 } else { 1 };
 
-    let ret =
-        unsafe { RegisterRawInputDevices(&rids, std::mem::size_of::<RAWINPUTDEVICE>() as u32) };
+    let ret = unsafe {
+        RegisterRawInputDevices(&rids[..count], std::mem::size_of::<RAWINPUTDEVICE>() as u32)
+    };
 
     if !ret.as_bool() {
         bail!(Error::with_chain(
@@ -134,15 +159,22 @@ pub fn log_key() -> Result<()> {
             "RegisterRawInputDevices() failed"
         ));
     }
+    Ok(())
+}
 
-    print!("RegisterRawInputDevices() returned {:?}", ret);
-
+/// Dispatch the messages of the current thread until `WM_QUIT`
+pub(crate) fn message_loop() {
     let mut msg: windows::Win32::UI::WindowsAndMessaging::MSG = unsafe { std::mem::zeroed() };
     while unsafe { GetMessageA(&mut msg, HWND::default(), 0, 0) }.as_bool() {
         unsafe { TranslateMessage(&msg) };
         unsafe { DispatchMessageA(&msg) };
     }
+}
 
+pub fn log_key() -> Result<()> {
+    let hwnd = message_window("KeyLogger\0", wnd_proc)?;
+    register_raw_input(hwnd, false)?;
+    message_loop();
     Ok(())
 }
 